@@ -0,0 +1,28 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "build/idl.rs"]
+mod idl;
+
+/// IDL files of the Hivemapper programs, with the `HivemapperProgram` variant they belong to and the prefix given to
+/// their instruction names so both programs share a single instruction enum.
+const IDLS: &[(&str, &str, &str)] = &[
+    ("idls/honey_program.json", "Program", ""),
+    ("idls/honey_lib.json", "Lib", "lib_"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build/idl.rs");
+
+    let mut instructions = vec![];
+    for (path, program, prefix) in IDLS {
+        println!("cargo:rerun-if-changed={}", path);
+        let content = fs::read_to_string(path).unwrap_or_else(|err| panic!("reading {}: {}", path, err));
+        instructions.extend(idl::parse(&content, path, program, prefix));
+    }
+    idl::check_first_bytes(&instructions);
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("idl.rs");
+    fs::write(out, idl::generate(&instructions)).unwrap();
+}
@@ -0,0 +1,273 @@
+//! Anchor IDL parsing and code generation of build.rs, included as a module by build.rs and tests/idl_codegen.rs.
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+/// Prefix of the account names only holding the position of an account whose role isn't known yet, no role constant
+/// is generated for them.
+const UNKNOWN_ACCOUNT_PREFIX: &str = "unknown_";
+
+pub enum Discriminator {
+    /// 8 bytes Anchor discriminator, `discriminator` key.
+    Anchor(Vec<u8>),
+    /// First byte of an instruction whose Anchor discriminator isn't known yet, `discriminator_first_byte` key.
+    FirstByte(u8),
+}
+
+impl Discriminator {
+    pub fn first_byte(&self) -> u8 {
+        match self {
+            Discriminator::Anchor(discriminator) => discriminator[0],
+            Discriminator::FirstByte(first_byte) => *first_byte,
+        }
+    }
+}
+
+pub struct IdlInstruction {
+    pub program: &'static str,
+    pub name: String,
+    pub prefixed_name: String,
+    pub discriminator: Discriminator,
+    pub accounts: Vec<String>,
+    pub args: Vec<(String, String)>,
+}
+
+/// Instructions of the IDL file at `path`, `program` being the `HivemapperProgram` variant they belong to and `prefix`
+/// the prefix given to their names.
+pub fn parse(content: &str, path: &str, program: &'static str, prefix: &str) -> Vec<IdlInstruction> {
+    let idl: Value = serde_json::from_str(content).unwrap_or_else(|err| panic!("parsing {}: {}", path, err));
+
+    let mut instructions = vec![];
+    for instruction in array(&idl, "instructions", path) {
+        let name = string(instruction, "name", path);
+        let discriminator = match (instruction.get("discriminator"), instruction.get("discriminator_first_byte")) {
+            (Some(_), None) => {
+                let discriminator = array(instruction, "discriminator", path)
+                    .iter()
+                    .map(byte)
+                    .collect::<Option<Vec<u8>>>()
+                    .unwrap_or_else(|| panic!("{}: invalid discriminator for instruction {}", path, name));
+                if discriminator.len() != 8 {
+                    panic!("{}: instruction {} discriminator must be the 8 bytes Anchor discriminator", path, name);
+                }
+                Discriminator::Anchor(discriminator)
+            }
+            (None, Some(first_byte)) => Discriminator::FirstByte(
+                byte(first_byte)
+                    .unwrap_or_else(|| panic!("{}: invalid discriminator_first_byte for instruction {}", path, name)),
+            ),
+            _ => panic!(
+                "{}: instruction {} needs either a discriminator or a discriminator_first_byte",
+                path, name
+            ),
+        };
+
+        let accounts = array(instruction, "accounts", path)
+            .iter()
+            .map(|account| string(account, "name", path))
+            .collect();
+        let args = array(instruction, "args", path)
+            .iter()
+            .map(|arg| (string(arg, "name", path), string(arg, "type", path)))
+            .collect();
+
+        instructions.push(IdlInstruction {
+            program,
+            prefixed_name: format!("{}{}", prefix, name),
+            name,
+            discriminator,
+            accounts,
+            args,
+        });
+    }
+
+    instructions
+}
+
+/// Instructions are only matched on their first byte while their Anchor discriminator isn't known, so the first byte
+/// of every instruction of a program must be unique.
+pub fn check_first_bytes(instructions: &[IdlInstruction]) {
+    for (index, instruction) in instructions.iter().enumerate() {
+        let first_byte = instruction.discriminator.first_byte();
+        if let Some(other) = instructions[..index]
+            .iter()
+            .find(|other| other.program == instruction.program && other.discriminator.first_byte() == first_byte)
+        {
+            panic!(
+                "instructions {} and {} of the {} IDL share the first discriminator byte {:#04x}",
+                other.name, instruction.name, instruction.program, first_byte
+            );
+        }
+    }
+}
+
+fn byte(value: &Value) -> Option<u8> {
+    value.as_u64().filter(|b| *b <= 0xff).map(|b| b as u8)
+}
+
+fn array<'a>(value: &'a Value, key: &str, path: &str) -> &'a Vec<Value> {
+    value[key]
+        .as_array()
+        .unwrap_or_else(|| panic!("{}: expected an array for {}", path, key))
+}
+
+fn string(value: &Value, key: &str, path: &str) -> String {
+    value[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("{}: expected a string for {}", path, key))
+        .to_string()
+}
+
+pub fn generate(instructions: &[IdlInstruction]) -> String {
+    let mut code = String::new();
+    writeln!(code, "// @generated by build.rs from the IDL files in idls/, do not edit.").unwrap();
+
+    writeln!(code, "\n#[derive(Clone, Copy, Debug, PartialEq, Eq)]").unwrap();
+    writeln!(code, "pub enum HivemapperInstruction {{").unwrap();
+    for instruction in instructions {
+        writeln!(code, "    {},", pascal_case(&instruction.prefixed_name)).unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+
+    writeln!(code, "\nimpl HivemapperInstruction {{").unwrap();
+    writeln!(code, "    /// Instruction name as declared in the IDL.").unwrap();
+    writeln!(code, "    pub fn name(&self) -> &'static str {{").unwrap();
+    writeln!(code, "        match self {{").unwrap();
+    for instruction in instructions {
+        let variant = pascal_case(&instruction.prefixed_name);
+        writeln!(code, "            Self::{} => {:?},", variant, instruction.name).unwrap();
+    }
+    writeln!(code, "        }}\n    }}").unwrap();
+    writeln!(code, "\n    /// Name of the account at `index` as declared in the IDL, matched on the `accounts` role constants.").unwrap();
+    writeln!(code, "    pub fn account_name(&self, index: usize) -> Option<&'static str> {{").unwrap();
+    writeln!(code, "        match (self, index) {{").unwrap();
+    for instruction in instructions {
+        let variant = pascal_case(&instruction.prefixed_name);
+        for (index, account) in instruction.accounts.iter().enumerate() {
+            if account.starts_with(UNKNOWN_ACCOUNT_PREFIX) {
+                writeln!(code, "            (Self::{}, {}) => Some({:?}),", variant, index, account).unwrap();
+            } else {
+                writeln!(
+                    code,
+                    "            (Self::{}, accounts::{}::{}) => Some({:?}),",
+                    variant,
+                    instruction.prefixed_name,
+                    account.to_uppercase(),
+                    account,
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(code, "            _ => None,").unwrap();
+    writeln!(code, "        }}\n    }}").unwrap();
+    writeln!(code, "\n    /// Arguments decoded as declared in the IDL, as (name, value) pairs in declaration order.").unwrap();
+    writeln!(code, "    pub fn decode_args(&self, data: &[u8]) -> Result<Vec<(&'static str, String)>, InstructionError> {{").unwrap();
+    writeln!(code, "        match self {{").unwrap();
+    for instruction in instructions {
+        let variant = pascal_case(&instruction.prefixed_name);
+        if instruction.args.is_empty() {
+            writeln!(code, "            Self::{} => Ok(vec![]),", variant).unwrap();
+            continue;
+        }
+        writeln!(code, "            Self::{} => {{", variant).unwrap();
+        writeln!(code, "                let args = {}Args::decode(data)?;", variant).unwrap();
+        let values: Vec<String> = instruction
+            .args
+            .iter()
+            .map(|(arg, _)| format!("({:?}, args.{}.to_string())", arg, arg))
+            .collect();
+        writeln!(code, "                Ok(vec![{}])", values.join(", ")).unwrap();
+        writeln!(code, "            }}").unwrap();
+    }
+    writeln!(code, "        }}\n    }}\n}}").unwrap();
+
+    writeln!(code, "\npub const REGISTRY: &[RegistryEntry] = &[").unwrap();
+    for instruction in instructions {
+        let discriminator = match &instruction.discriminator {
+            Discriminator::Anchor(discriminator) => format!("Discriminator::Anchor({:?})", discriminator),
+            Discriminator::FirstByte(first_byte) => format!("Discriminator::FirstByte({})", first_byte),
+        };
+        writeln!(
+            code,
+            "    RegistryEntry {{ program: HivemapperProgram::{}, discriminator: {}, instruction: HivemapperInstruction::{} }},",
+            instruction.program,
+            discriminator,
+            pascal_case(&instruction.prefixed_name),
+        )
+        .unwrap();
+    }
+    writeln!(code, "];").unwrap();
+
+    writeln!(code, "\npub mod accounts {{").unwrap();
+    for instruction in instructions {
+        let roles: Vec<(usize, &String)> = instruction
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| !account.starts_with(UNKNOWN_ACCOUNT_PREFIX))
+            .collect();
+        if roles.is_empty() {
+            continue;
+        }
+
+        writeln!(code, "    pub mod {} {{", instruction.prefixed_name).unwrap();
+        for (index, account) in roles {
+            writeln!(code, "        pub const {}: usize = {};", account.to_uppercase(), index).unwrap();
+        }
+        writeln!(code, "    }}").unwrap();
+    }
+    writeln!(code, "}}").unwrap();
+
+    for instruction in instructions.iter().filter(|instruction| !instruction.args.is_empty()) {
+        let name = format!("{}Args", pascal_case(&instruction.prefixed_name));
+
+        writeln!(code, "\n#[derive(Clone, Debug, PartialEq)]").unwrap();
+        writeln!(code, "pub struct {} {{", name).unwrap();
+        for (arg, ty) in &instruction.args {
+            writeln!(code, "    pub {}: {},", arg, rust_type(ty)).unwrap();
+        }
+        writeln!(code, "}}").unwrap();
+
+        writeln!(code, "\nimpl {} {{", name).unwrap();
+        writeln!(code, "    pub fn decode(data: &[u8]) -> Result<Self, InstructionError> {{").unwrap();
+        writeln!(code, "        let mut args = ArgsReader::new(data);").unwrap();
+        writeln!(code, "        Ok({} {{", name).unwrap();
+        for (arg, ty) in &instruction.args {
+            writeln!(code, "            {}: args.{}()?,", arg, reader_method(ty)).unwrap();
+        }
+        writeln!(code, "        }})\n    }}\n}}").unwrap();
+    }
+
+    code
+}
+
+/// Argument types used by the IDL files, a new type needs its `ArgsReader` method in src/args.rs.
+fn rust_type(idl_type: &str) -> &'static str {
+    match idl_type {
+        "u64" => "u64",
+        "i64" => "i64",
+        other => panic!("unsupported IDL argument type {}", other),
+    }
+}
+
+fn reader_method(idl_type: &str) -> &'static str {
+    match idl_type {
+        "u64" => "u64",
+        "i64" => "i64",
+        other => panic!("unsupported IDL argument type {}", other),
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+create table if not exists fleets
+(
+    addr text not null constraint fleets_pk primary key
+);
+
+create table if not exists drivers
+(
+    addr text not null constraint drivers_pk primary key
+);
+
+create table if not exists fleet_drivers
+(
+    id         text not null constraint fleet_drivers_pk primary key,
+    fleet_addr text not null,
+    addr       text not null
+);
+
+create table if not exists payments
+(
+    id       text      not null constraint payments_pk primary key,
+    trx_id   text      not null,
+    date     timestamp not null,
+    "to"     text      not null,
+    to_owner text      not null,
+    amount   numeric   not null,
+    type     text      not null
+);
+
+create table if not exists split_payments
+(
+    id            text      not null constraint split_payments_pk primary key,
+    trx_id        text      not null,
+    date          timestamp not null,
+    fleet         text      not null,
+    fleet_amount  numeric   not null,
+    driver        text      not null,
+    driver_amount numeric   not null,
+    variant       text      not null
+);
+
+create table if not exists transfers
+(
+    id         text      not null constraint transfers_pk primary key,
+    trx_id     text      not null,
+    date       timestamp not null,
+    "from"     text      not null,
+    from_owner text      not null,
+    "to"       text      not null,
+    to_owner   text      not null,
+    amount     numeric   not null
+);
+
+create index if not exists payments_to_idx on payments ("to");
+create index if not exists payments_to_owner_idx on payments (to_owner);
+create index if not exists transfers_from_owner_idx on transfers (from_owner);
+create index if not exists transfers_to_owner_idx on transfers (to_owner);
+create index if not exists fleet_drivers_fleet_addr_idx on fleet_drivers (fleet_addr);
@@ -0,0 +1,42 @@
+use crate::error::InstructionError;
+
+/// Length of the Anchor discriminator prefixing every Hivemapper instruction data.
+pub const ANCHOR_DISCRIMINATOR_LEN: usize = 8;
+
+/// Reads the Borsh encoded arguments following the Anchor discriminator of an instruction.
+pub struct ArgsReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ArgsReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ArgsReader {
+            data,
+            offset: ANCHOR_DISCRIMINATOR_LEN,
+        }
+    }
+
+    pub fn u64(&mut self) -> Result<u64, InstructionError> {
+        Ok(u64::from_le_bytes(self.take::<8>()?))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, InstructionError> {
+        Ok(i64::from_le_bytes(self.take::<8>()?))
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], InstructionError> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + N)
+            .ok_or(InstructionError::TruncatedArguments {
+                offset: self.offset,
+                len: self.data.len(),
+            })?;
+        self.offset += N;
+
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        Ok(out)
+    }
+}
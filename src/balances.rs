@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Neg;
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{balance_change, BalanceChange, BalanceChanges, Transactions};
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::scalar::BigInt;
+use substreams::store::{DeltaBigInt, Deltas, StoreAdd, StoreAddBigInt, StoreGet, StoreGetString, StoreNew};
+
+/// Running HONEY balances in base units, keyed by `account:<token account>` and `owner:<wallet>`. When a token account
+/// changes owner its balance moves from the old owner to the new one.
+#[substreams::handlers::store]
+pub fn store_balances(transactions: Transactions, owners: StoreGetString, store: StoreAddBigInt) {
+    for (ordinal, entry) in balance_entries(&transactions, |account| owners.get_first(account)).into_iter().enumerate() {
+        let ordinal = ordinal as u64;
+        match entry {
+            BalanceEntry::Delta { account, owner, amount } => {
+                store.add(ordinal, account_key(&account), &amount);
+                if !owner.is_empty() {
+                    store.add(ordinal, owner_key(&owner), &amount);
+                }
+            }
+            BalanceEntry::OwnerChange { old_owner, new_owner, amount } => {
+                if amount.is_zero() {
+                    continue;
+                }
+                if !old_owner.is_empty() {
+                    store.add(ordinal, owner_key(&old_owner), &amount.clone().neg());
+                }
+                if !new_owner.is_empty() {
+                    store.add(ordinal, owner_key(&new_owner), &amount);
+                }
+            }
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_balance_changes(
+    clock: Clock,
+    transactions: Transactions,
+    owners: StoreGetString,
+    deltas: Deltas<DeltaBigInt>,
+) -> Result<BalanceChanges, Error> {
+    // store_balances uses the entry index as ordinal, so each account delta maps back to the entry that produced it
+    let entries = balance_entries(&transactions, |account| owners.get_first(account));
+
+    let balance_changes = deltas
+        .deltas
+        .into_iter()
+        .filter_map(|delta| {
+            let (r#type, account, owner) = match delta.key.split_once(':')? {
+                ("account", account) => {
+                    let owner = match entries.get(delta.ordinal as usize) {
+                        Some(BalanceEntry::Delta { owner, .. }) => owner.clone(),
+                        _ => String::new(),
+                    };
+                    (balance_change::Type::TokenAccount, account.to_string(), owner)
+                }
+                ("owner", owner) => (balance_change::Type::Owner, String::new(), owner.to_string()),
+                _ => return None,
+            };
+
+            Some(BalanceChange {
+                r#type: r#type.into(),
+                account,
+                owner,
+                delta: (delta.new_value.clone() - delta.old_value.clone()).to_string(),
+                old_balance: delta.old_value.to_string(),
+                new_balance: delta.new_value.to_string(),
+                slot: clock.number,
+                ordinal: delta.ordinal,
+            })
+        })
+        .collect();
+
+    Ok(BalanceChanges { balance_changes })
+}
+
+#[derive(Debug, PartialEq)]
+enum BalanceEntry {
+    /// Signed change of a token account balance, `owner` owning the account at the time of the change.
+    Delta { account: String, owner: String, amount: BigInt },
+    /// Token account balance `amount` moving from `old_owner` to `new_owner`.
+    OwnerChange { old_owner: String, new_owner: String, amount: BigInt },
+}
+
+/// Balance entries in the order they happened in the block, `stored_owner` returning the owner of a token account
+/// at the start of the block from the owners store.
+fn balance_entries<F: Fn(&str) -> Option<String>>(transactions: &Transactions, stored_owner: F) -> Vec<BalanceEntry> {
+    let mut builder = BalanceEntries {
+        owners: Owners::new(transactions, stored_owner),
+        trx_deltas: HashMap::new(),
+        entries: vec![],
+    };
+
+    for trx in transactions.transactions.iter() {
+        builder.trx_deltas.clear();
+        for instruction in trx.instructions.iter() {
+            match &instruction.item {
+                Some(Item::Mint(mint)) => {
+                    builder.delta(&mint.to, &mint.to_owner, BigInt::from(mint.amount_raw));
+                }
+                Some(Item::Burn(burn)) => {
+                    builder.delta(&burn.from, &burn.from_owner, BigInt::from(burn.amount_raw).neg());
+                }
+                Some(Item::Transfer(transfer)) => {
+                    let amount = BigInt::from(transfer.amount_raw);
+                    builder.delta(&transfer.from, &transfer.from_owner, amount.clone().neg());
+                    builder.delta(&transfer.to, &transfer.to_owner, amount);
+                }
+                Some(Item::InitializedAccount(initialized_account)) => {
+                    builder.owners.set(&initialized_account.account, &initialized_account.owner);
+                }
+                Some(Item::AccountOwnerChanged(account_owner_changed)) => {
+                    builder.owner_change(
+                        &account_owner_changed.account,
+                        &account_owner_changed.old_owner,
+                        &account_owner_changed.new_owner,
+                        account_owner_changed.balance_raw,
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    builder.entries
+}
+
+struct BalanceEntries<F> {
+    owners: Owners<F>,
+    // balance changes of each account in the current transaction, owner changes only carry the balance held at the
+    // start of the transaction
+    trx_deltas: HashMap<String, BigInt>,
+    entries: Vec<BalanceEntry>,
+}
+
+impl<F: Fn(&str) -> Option<String>> BalanceEntries<F> {
+    fn delta(&mut self, account: &str, event_owner: &str, amount: BigInt) {
+        let trx_delta = self.trx_deltas.entry(account.to_string()).or_insert_with(BigInt::zero);
+        *trx_delta = trx_delta.clone() + amount.clone();
+
+        self.entries.push(BalanceEntry::Delta {
+            account: account.to_string(),
+            owner: self.owners.resolve(account, event_owner),
+            amount,
+        });
+    }
+
+    fn owner_change(&mut self, account: &str, old_owner: &str, new_owner: &str, balance_raw: u64) {
+        let trx_delta = self.trx_deltas.get(account).cloned().unwrap_or_else(BigInt::zero);
+
+        self.entries.push(BalanceEntry::OwnerChange {
+            old_owner: self.owners.resolve(account, old_owner),
+            new_owner: new_owner.to_string(),
+            amount: BigInt::from(balance_raw) + trx_delta,
+        });
+        self.owners.set(account, new_owner);
+    }
+}
+
+/// Owner of each token account as the block is replayed, starting from the owners store as it was at the start of the
+/// block. Accounts re-assigned or closed in the block start with the owner reported by their first owner change or
+/// close when it has one. An account closed in the block is removed from the store, read as of the start of the
+/// block it still reports the owner.
+struct Owners<F> {
+    current: HashMap<String, String>,
+    stored_owner: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Owners<F> {
+    fn new(transactions: &Transactions, stored_owner: F) -> Self {
+        let mut seen = HashSet::new();
+        let mut current = HashMap::new();
+        for trx in transactions.transactions.iter() {
+            for instruction in trx.instructions.iter() {
+                let (account, start_owner) = match &instruction.item {
+                    Some(Item::InitializedAccount(initialized_account)) => (&initialized_account.account, None),
+                    Some(Item::AccountOwnerChanged(account_owner_changed)) => {
+                        (&account_owner_changed.account, Some(&account_owner_changed.old_owner))
+                    }
+                    Some(Item::ClosedAccount(closed_account)) => (&closed_account.account, Some(&closed_account.owner)),
+                    _ => continue,
+                };
+                if !seen.insert(account.clone()) {
+                    continue;
+                }
+                if let Some(owner) = start_owner.filter(|owner| !owner.is_empty()) {
+                    current.insert(account.clone(), owner.clone());
+                }
+            }
+        }
+
+        Owners { current, stored_owner }
+    }
+
+    fn set(&mut self, account: &str, owner: &str) {
+        self.current.insert(account.to_string(), owner.to_string());
+    }
+
+    /// Owner known at this point of the block first. Events report the owner found in the transaction token
+    /// balances, which is the owner at the end of the transaction, so it only comes next, then the owners store.
+    fn resolve(&self, account: &str, event_owner: &str) -> String {
+        if let Some(owner) = self.current.get(account) {
+            return owner.clone();
+        }
+        if !event_owner.is_empty() {
+            return event_owner.to_string();
+        }
+        (self.stored_owner)(account).unwrap_or_default()
+    }
+}
+
+fn account_key(account: &str) -> String {
+    format!("account:{}", account)
+}
+
+fn owner_key(owner: &str) -> String {
+    format!("owner:{}", owner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{balance_entries, BalanceEntry};
+    use crate::pb::hivemapper::types::v1::instruction::Item;
+    use crate::pb::hivemapper::types::v1::{
+        AccountOwnerChanged, InitializedAccount, Instruction, Mint, Transaction, Transactions, Transfer,
+    };
+    use std::collections::HashMap;
+    use substreams::scalar::BigInt;
+
+    fn transaction(items: Vec<Item>) -> Transaction {
+        Transaction {
+            instructions: items
+                .into_iter()
+                .map(|item| Instruction {
+                    item: Some(item),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn mint(to: &str, to_owner: &str, amount_raw: u64) -> Item {
+        Item::Mint(Mint {
+            to: to.to_string(),
+            to_owner: to_owner.to_string(),
+            amount_raw,
+            ..Default::default()
+        })
+    }
+
+    fn transfer(from: &str, from_owner: &str, to: &str, to_owner: &str, amount_raw: u64) -> Item {
+        Item::Transfer(Transfer {
+            from: from.to_string(),
+            from_owner: from_owner.to_string(),
+            to: to.to_string(),
+            to_owner: to_owner.to_string(),
+            amount_raw,
+            ..Default::default()
+        })
+    }
+
+    fn owner_changed(account: &str, old_owner: &str, new_owner: &str, balance_raw: u64) -> Item {
+        Item::AccountOwnerChanged(AccountOwnerChanged {
+            account: account.to_string(),
+            old_owner: old_owner.to_string(),
+            new_owner: new_owner.to_string(),
+            balance_raw,
+        })
+    }
+
+    fn delta(account: &str, owner: &str, amount: i64) -> BalanceEntry {
+        BalanceEntry::Delta {
+            account: account.to_string(),
+            owner: owner.to_string(),
+            amount: BigInt::from(amount),
+        }
+    }
+
+    fn owner_change(old_owner: &str, new_owner: &str, amount: i64) -> BalanceEntry {
+        BalanceEntry::OwnerChange {
+            old_owner: old_owner.to_string(),
+            new_owner: new_owner.to_string(),
+            amount: BigInt::from(amount),
+        }
+    }
+
+    fn entries(transactions: Vec<Transaction>, stored_owners: &[(&str, &str)]) -> Vec<BalanceEntry> {
+        let stored_owners: HashMap<String, String> = stored_owners
+            .iter()
+            .map(|(account, owner)| (account.to_string(), owner.to_string()))
+            .collect();
+        let transactions = Transactions {
+            transactions,
+            ..Default::default()
+        };
+        balance_entries(&transactions, |account| stored_owners.get(account).cloned())
+    }
+
+    #[test]
+    fn transfer_owners_from_events_then_owners_store() {
+        let entries = entries(
+            vec![transaction(vec![transfer("a", "", "b", "bob", 30)])],
+            &[("a", "alice"), ("b", "stale")],
+        );
+
+        assert_eq!(entries, vec![delta("a", "alice", -30), delta("b", "bob", 30)]);
+    }
+
+    #[test]
+    fn mint_before_owner_change_in_the_same_transaction_goes_to_the_old_owner() {
+        // the mint event reports the owner found in the post token balances, the new owner
+        let entries = entries(
+            vec![transaction(vec![mint("a", "bob", 100), owner_changed("a", "alice", "bob", 50)])],
+            &[("a", "bob")],
+        );
+
+        assert_eq!(entries, vec![delta("a", "alice", 100), owner_change("alice", "bob", 150)]);
+    }
+
+    #[test]
+    fn mint_after_owner_change_goes_to_the_new_owner() {
+        let entries = entries(
+            vec![
+                transaction(vec![owner_changed("a", "alice", "bob", 10)]),
+                transaction(vec![mint("a", "", 5)]),
+            ],
+            &[("a", "bob")],
+        );
+
+        assert_eq!(entries, vec![owner_change("alice", "bob", 10), delta("a", "bob", 5)]);
+    }
+
+    #[test]
+    fn transfer_out_before_owner_change_reduces_the_moved_balance() {
+        let entries = entries(
+            vec![transaction(vec![
+                transfer("a", "bob", "b", "carol", 40),
+                owner_changed("a", "alice", "bob", 100),
+            ])],
+            &[("a", "bob"), ("b", "carol")],
+        );
+
+        assert_eq!(
+            entries,
+            vec![delta("a", "alice", -40), delta("b", "carol", 40), owner_change("alice", "bob", 60)]
+        );
+    }
+
+    #[test]
+    fn initialized_account_owner_applies_to_later_events() {
+        let entries = entries(
+            vec![transaction(vec![
+                Item::InitializedAccount(InitializedAccount {
+                    account: "c".to_string(),
+                    mint: "honey".to_string(),
+                    owner: "carol".to_string(),
+                }),
+                mint("c", "", 7),
+            ])],
+            &[],
+        );
+
+        assert_eq!(entries, vec![delta("c", "carol", 7)]);
+    }
+}
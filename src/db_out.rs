@@ -0,0 +1,75 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{mint, Transactions};
+use crate::utils::{event_id, split_payments};
+use substreams::errors::Error;
+use substreams_database_change::pb::database::DatabaseChanges;
+use substreams_database_change::tables::Tables;
+
+/// Maps the decoded events to the `schema.sql` tables, primary keys are derived from the transaction hash
+/// and instruction position so replaying a block upserts the same rows.
+#[substreams::handlers::map]
+pub fn db_out(transactions: Transactions) -> Result<DatabaseChanges, Error> {
+    let mut tables = Tables::new();
+    let timestamp = transactions
+        .block
+        .as_ref()
+        .and_then(|block| block.timestamp.clone())
+        .unwrap_or_default();
+
+    for trx in transactions.transactions.iter() {
+        for instruction in trx.instructions.iter() {
+            let id = event_id(&trx.trx_hash, instruction);
+            match &instruction.item {
+                Some(Item::Mint(mint)) => {
+                    tables
+                        .upsert_row("payments", &id)
+                        .set("trx_id", &trx.trx_hash)
+                        .set("date", &timestamp)
+                        .set("to", &mint.to)
+                        .set("to_owner", &mint.to_owner)
+                        .set("amount", &mint.amount_decimal)
+                        .set("type", mint.r#type().as_str_name());
+
+                    if mint.r#type() == mint::Type::RegularDriver {
+                        tables.upsert_row("drivers", &mint.to).set("addr", &mint.to);
+                    }
+                }
+                Some(Item::Transfer(transfer)) => {
+                    tables
+                        .upsert_row("transfers", &id)
+                        .set("trx_id", &trx.trx_hash)
+                        .set("date", &timestamp)
+                        .set("from", &transfer.from)
+                        .set("from_owner", &transfer.from_owner)
+                        .set("to", &transfer.to)
+                        .set("to_owner", &transfer.to_owner)
+                        .set("amount", &transfer.amount_decimal);
+                }
+                _ => {}
+            }
+        }
+
+        for (id, split_payment) in split_payments(trx) {
+            let fleet = &split_payment.fleet;
+            let driver = &split_payment.driver;
+
+            tables.upsert_row("fleets", fleet).set("addr", fleet);
+            tables.upsert_row("drivers", driver).set("addr", driver);
+            tables
+                .upsert_row("fleet_drivers", format!("{}:{}", fleet, driver))
+                .set("fleet_addr", fleet)
+                .set("addr", driver);
+            tables
+                .upsert_row("split_payments", &id)
+                .set("trx_id", &trx.trx_hash)
+                .set("date", &timestamp)
+                .set("fleet", fleet)
+                .set("fleet_amount", &split_payment.fleet_amount_decimal)
+                .set("driver", driver)
+                .set("driver_amount", &split_payment.driver_amount_decimal)
+                .set("variant", split_payment.variant().as_str_name());
+        }
+    }
+
+    Ok(tables.to_database_changes())
+}
@@ -0,0 +1,38 @@
+use thiserror::Error;
+use crate::instructions::HivemapperInstruction;
+
+#[derive(Error, Debug)]
+pub enum InstructionError {
+    #[error("expected instruction of program {expected} got {actual}")]
+    UnexpectedProgram { expected: String, actual: String },
+
+    #[error("unexpected instruction {0:?} for this program")]
+    UnexpectedInstruction(HivemapperInstruction),
+
+    #[error("unexpected inner instructions count {0}")]
+    UnexpectedInnerInstructionsCount(usize),
+
+    #[error("missing inner instruction at index {0}")]
+    MissingInnerInstruction(usize),
+
+    #[error("missing account at index {0}")]
+    MissingAccount(usize),
+
+    #[error("instruction arguments truncated at offset {offset}, data length {len}")]
+    TruncatedArguments { offset: usize, len: usize },
+
+    #[error("unpacking token instruction: {0}")]
+    TokenInstruction(String),
+
+    #[error("expecting a {0} token instruction")]
+    UnexpectedTokenInstruction(&'static str),
+
+    #[error("unknown token instruction pairing {first} and {second}")]
+    UnknownInstructionPairing { first: &'static str, second: &'static str },
+
+    #[error("mint to {to} matches neither fleet {fleet} nor driver {driver}")]
+    UnmatchedFleetMint { to: String, fleet: String, driver: String },
+
+    #[error("missing fleet mints, manager found {manager}, driver found {driver}")]
+    MissingFleetMints { manager: bool, driver: bool },
+}
@@ -1,4 +1,4 @@
-use crate::pb::hivemapper::types::v1::{Burn, InitializedAccount, Mint, Transfer};
+use crate::pb::hivemapper::types::v1::{AccountOwnerChanged, Burn, ClosedAccount, InitializedAccount, Mint, Transfer};
 
 pub struct Event {
     pub r#type: Type,
@@ -9,4 +9,6 @@ pub enum Type {
     Burn(Burn),
     Transfer(Transfer),
     InitializeAccount(InitializedAccount),
+    AccountOwnerChanged(AccountOwnerChanged),
+    ClosedAccount(ClosedAccount),
 }
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use crate::pb::hivemapper::types::v1::{FleetDriver, FleetDrivers, Transactions};
+use crate::utils::split_payments;
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::store::{
+    StoreAdd, StoreAddInt64, StoreGet, StoreGetInt64, StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsInt64,
+};
+
+/// Slot at which each fleet driver was first paid through its fleet, keyed by `<fleet>:<driver>`.
+#[substreams::handlers::store]
+pub fn store_fleet_drivers_first_seen(transactions: Transactions, store: StoreSetIfNotExistsInt64) {
+    let slot = transactions.block.as_ref().map(|block| block.slot).unwrap_or_default();
+    for (ordinal, key) in fleet_driver_keys(&transactions).into_iter().enumerate() {
+        store.set_if_not_exists(ordinal as u64, key, &(slot as i64));
+    }
+}
+
+/// Number of split payments made to each fleet driver through its fleet, keyed by `<fleet>:<driver>`.
+#[substreams::handlers::store]
+pub fn store_fleet_drivers_payment_count(transactions: Transactions, store: StoreAddInt64) {
+    for (ordinal, key) in fleet_driver_keys(&transactions).into_iter().enumerate() {
+        store.add(ordinal as u64, key, 1);
+    }
+}
+
+/// Fleet driver memberships paid in the block, with their first and last seen slots and payment count.
+#[substreams::handlers::map]
+pub fn map_fleet_drivers(
+    clock: Clock,
+    transactions: Transactions,
+    first_seen: StoreGetInt64,
+    payment_count: StoreGetInt64,
+) -> Result<FleetDrivers, Error> {
+    let mut fleet_drivers: BTreeMap<String, FleetDriver> = BTreeMap::new();
+    for trx in transactions.transactions.iter() {
+        for (_, split_payment) in split_payments(trx) {
+            let key = fleet_driver_key(&split_payment.fleet, &split_payment.driver);
+            fleet_drivers.entry(key.clone()).or_insert_with(|| FleetDriver {
+                fleet: split_payment.fleet.clone(),
+                driver: split_payment.driver.clone(),
+                first_seen_slot: first_seen.get_last(&key).unwrap_or_default() as u64,
+                last_seen_slot: clock.number,
+                payment_count: payment_count.get_last(&key).unwrap_or_default() as u64,
+            });
+        }
+    }
+
+    Ok(FleetDrivers {
+        fleet_drivers: fleet_drivers.into_values().collect(),
+    })
+}
+
+fn fleet_driver_keys(transactions: &Transactions) -> Vec<String> {
+    transactions
+        .transactions
+        .iter()
+        .flat_map(|trx| {
+            split_payments(trx)
+                .map(|(_, split_payment)| fleet_driver_key(&split_payment.fleet, &split_payment.driver))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn fleet_driver_key(fleet: &str, driver: &str) -> String {
+    format!("{}:{}", fleet, driver)
+}
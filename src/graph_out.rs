@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{mint, Transactions};
+use crate::utils::{event_id, split_payments};
+use substreams::errors::Error;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{DeltaInt64, Deltas, StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsInt64};
+use substreams_entity_change::pb::entity::EntityChanges;
+use substreams_entity_change::tables::Tables;
+
+/// Slot at which each `Fleet` and `Driver` entity was first written, keyed by `<entity>:<id>`.
+#[substreams::handlers::store]
+pub fn store_graph_entities_first_seen(transactions: Transactions, store: StoreSetIfNotExistsInt64) {
+    let slot = transactions.block.as_ref().map(|block| block.slot).unwrap_or_default();
+    for (ordinal, (entity, id, _)) in owned_entities(&transactions).into_iter().enumerate() {
+        store.set_if_not_exists(ordinal as u64, entity_key(entity, id), &(slot as i64));
+    }
+}
+
+/// Maps the decoded events to the `schema.graphql` entities, entity IDs are derived from the transaction hash
+/// and instruction position.
+#[substreams::handlers::map]
+pub fn graph_out(transactions: Transactions, first_seen: Deltas<DeltaInt64>) -> Result<EntityChanges, Error> {
+    let mut tables = Tables::new();
+    let (slot, timestamp) = match &transactions.block {
+        Some(block) => (block.slot, block.timestamp.as_ref().map(|t| t.seconds).unwrap_or_default()),
+        None => (0, 0),
+    };
+
+    for trx in transactions.transactions.iter() {
+        for instruction in trx.instructions.iter() {
+            let id = event_id(&trx.trx_hash, instruction);
+            match &instruction.item {
+                Some(Item::Mint(mint)) => {
+                    let row = tables
+                        .create_row("Reward", &id)
+                        .set("trxHash", &trx.trx_hash)
+                        .set("slot", BigInt::from(slot))
+                        .set("timestamp", BigInt::from(timestamp))
+                        .set("to", &mint.to)
+                        .set("toOwner", &mint.to_owner)
+                        .set("type", mint.r#type().as_str_name().to_string())
+                        .set("amount", decimal(&mint.amount_decimal))
+                        .set("amountRaw", BigInt::from(mint.amount_raw));
+
+                    match mint.r#type() {
+                        mint::Type::RegularDriver | mint::Type::FleetDriver => {
+                            row.set("driver", &mint.to);
+                        }
+                        mint::Type::FleetManager => {
+                            row.set("fleet", &mint.to);
+                        }
+                        _ => {}
+                    }
+                }
+                Some(Item::Burn(burn)) => {
+                    tables
+                        .create_row("Burn", &id)
+                        .set("trxHash", &trx.trx_hash)
+                        .set("slot", BigInt::from(slot))
+                        .set("timestamp", BigInt::from(timestamp))
+                        .set("from", &burn.from)
+                        .set("fromOwner", &burn.from_owner)
+                        .set("authority", &burn.authority)
+                        .set("type", burn.r#type().as_str_name().to_string())
+                        .set("amount", decimal(&burn.amount_decimal))
+                        .set("amountRaw", BigInt::from(burn.amount_raw));
+                }
+                Some(Item::Transfer(transfer)) => {
+                    tables
+                        .create_row("Transfer", &id)
+                        .set("trxHash", &trx.trx_hash)
+                        .set("slot", BigInt::from(slot))
+                        .set("timestamp", BigInt::from(timestamp))
+                        .set("from", &transfer.from)
+                        .set("fromOwner", &transfer.from_owner)
+                        .set("to", &transfer.to)
+                        .set("toOwner", &transfer.to_owner)
+                        .set("amount", decimal(&transfer.amount_decimal))
+                        .set("amountRaw", BigInt::from(transfer.amount_raw));
+                }
+                _ => {}
+            }
+        }
+
+        for (id, split_payment) in split_payments(trx) {
+            let fleet = &split_payment.fleet;
+            let driver = &split_payment.driver;
+            tables
+                .update_row("FleetDriver", format!("{}:{}", fleet, driver))
+                .set("fleet", fleet)
+                .set("driver", driver);
+            tables
+                .create_row("SplitPayment", id)
+                .set("trxHash", &trx.trx_hash)
+                .set("slot", BigInt::from(slot))
+                .set("timestamp", BigInt::from(timestamp))
+                .set("fleet", fleet)
+                .set("fleetAmount", decimal(&split_payment.fleet_amount_decimal))
+                .set("driver", driver)
+                .set("driverAmount", decimal(&split_payment.driver_amount_decimal))
+                .set("variant", split_payment.variant().as_str_name().to_string());
+        }
+    }
+
+    // the zero amount side of a payment often has no token balance entry, an empty owner only goes to an entity
+    // created in this block, the schema requires one
+    let mut created: HashSet<String> = first_seen.deltas.into_iter().map(|delta| delta.key).collect();
+    for (entity, id, owner) in owned_entities(&transactions) {
+        if created.remove(&entity_key(entity, id)) || !owner.is_empty() {
+            tables.update_row(entity, id).set("owner", owner.to_string());
+        }
+    }
+
+    Ok(tables.to_entity_changes())
+}
+
+/// `Fleet` and `Driver` entities written by the block events in order, with the owner reported by the event.
+fn owned_entities(transactions: &Transactions) -> Vec<(&'static str, &str, &str)> {
+    let mut entities = vec![];
+    for trx in transactions.transactions.iter() {
+        for instruction in trx.instructions.iter() {
+            if let Some(Item::Mint(mint)) = &instruction.item {
+                match mint.r#type() {
+                    mint::Type::RegularDriver | mint::Type::FleetDriver => {
+                        entities.push(("Driver", mint.to.as_str(), mint.to_owner.as_str()))
+                    }
+                    mint::Type::FleetManager => entities.push(("Fleet", mint.to.as_str(), mint.to_owner.as_str())),
+                    _ => {}
+                }
+            }
+        }
+        for (_, split_payment) in split_payments(trx) {
+            entities.push(("Fleet", split_payment.fleet.as_str(), split_payment.fleet_owner.as_str()));
+            entities.push(("Driver", split_payment.driver.as_str(), split_payment.driver_owner.as_str()));
+        }
+    }
+    entities
+}
+
+fn entity_key(entity: &str, id: &str) -> String {
+    format!("{}:{}", entity, id)
+}
+
+fn decimal(amount: &str) -> BigDecimal {
+    BigDecimal::try_from(amount).unwrap_or_else(|_| BigDecimal::zero())
+}
@@ -0,0 +1,8 @@
+//! Instruction enum, discriminator registry, account roles and argument structs generated by build.rs from the
+//! Anchor IDL files in idls/. Adding an instruction to an IDL file makes it known to the decoder.
+
+use crate::args::ArgsReader;
+use crate::error::InstructionError;
+use crate::instructions::{Discriminator, HivemapperProgram, RegistryEntry};
+
+include!(concat!(env!("OUT_DIR"), "/idl.rs"));
@@ -0,0 +1,158 @@
+use crate::args::ANCHOR_DISCRIMINATOR_LEN;
+use crate::params::Params;
+
+pub use crate::idl::{HivemapperInstruction, REGISTRY};
+
+/// Hivemapper programs described by an IDL file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HivemapperProgram {
+    Program,
+    Lib,
+}
+
+impl HivemapperProgram {
+    /// Resolves the program deployed at `program_id`, program ids come from the params so devnet or test validator
+    /// deployments are recognized as well.
+    pub fn from_program_id(params: &Params, program_id: &str) -> Option<HivemapperProgram> {
+        if program_id == params.program_id {
+            Some(HivemapperProgram::Program)
+        } else if program_id == params.lib_program_id {
+            Some(HivemapperProgram::Lib)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct RegistryEntry {
+    pub program: HivemapperProgram,
+    pub discriminator: Discriminator,
+    pub instruction: HivemapperInstruction,
+}
+
+pub enum Discriminator {
+    /// Full 8 bytes Anchor discriminator.
+    Anchor([u8; ANCHOR_DISCRIMINATOR_LEN]),
+    /// Only the first byte is known until the Anchor discriminator is taken from a real transaction, see
+    /// idls/README.md.
+    FirstByte(u8),
+}
+
+impl Discriminator {
+    pub fn first_byte(&self) -> u8 {
+        match self {
+            Discriminator::Anchor(discriminator) => discriminator[0],
+            Discriminator::FirstByte(first_byte) => *first_byte,
+        }
+    }
+}
+
+/// How an instruction data was matched to its registry entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Match {
+    Anchor,
+    FirstByte,
+}
+
+/// Finds the registry entry of the given program instruction data. Entries are matched on the full 8 bytes Anchor
+/// discriminator first, then on the first byte. The Anchor discriminators are derived from guessed instruction names,
+/// see idls/README.md, so a wrong guess still matches its instruction through that fallback.
+pub fn lookup(params: &Params, program_id: &str, data: &[u8]) -> Option<(&'static RegistryEntry, Match)> {
+    let program = HivemapperProgram::from_program_id(params, program_id)?;
+    let entries = || REGISTRY.iter().filter(move |entry| entry.program == program);
+
+    if let Some(discriminator) = data.get(..ANCHOR_DISCRIMINATOR_LEN) {
+        let anchor_entry = entries().find(|entry| match &entry.discriminator {
+            Discriminator::Anchor(entry_discriminator) => entry_discriminator == discriminator,
+            Discriminator::FirstByte(_) => false,
+        });
+        if let Some(entry) = anchor_entry {
+            return Some((entry, Match::Anchor));
+        }
+    }
+
+    // build.rs makes sure first bytes are unique within a program
+    let first_byte = *data.first()?;
+    entries()
+        .find(|entry| entry.discriminator.first_byte() == first_byte)
+        .map(|entry| (entry, Match::FirstByte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNKNOWN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+    fn lookup_program(program_id: &str, data: &[u8]) -> Option<(HivemapperInstruction, Match)> {
+        lookup(&Params::default(), program_id, data).map(|(entry, matched)| (entry.instruction, matched))
+    }
+
+    fn program(data: &[u8]) -> Option<(HivemapperInstruction, Match)> {
+        lookup_program(crate::constants::HONEY_TOKEN_INSTRUCTION_PROGRAM, data)
+    }
+
+    fn lib(data: &[u8]) -> Option<(HivemapperInstruction, Match)> {
+        lookup_program(crate::constants::HONEY_TOKEN_INSTRUCTION_LIB, data)
+    }
+
+    #[test]
+    fn lookup_anchor_discriminator() {
+        assert_eq!(
+            program(&[151, 57, 10, 118, 150, 29, 36, 58]),
+            Some((HivemapperInstruction::PayTo, Match::Anchor))
+        );
+        // arguments following the discriminator don't matter
+        assert_eq!(
+            lib(&[232, 254, 209, 244, 123, 89, 154, 207, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]),
+            Some((HivemapperInstruction::LibInitializeGlobalState, Match::Anchor))
+        );
+    }
+
+    #[test]
+    fn lookup_first_byte_collisions_between_program_and_lib() {
+        // 0xf1 is the program pay_operational_reward and the lib mint_to
+        assert_eq!(
+            program(&[241, 25, 201, 9, 42, 5, 121, 159]),
+            Some((HivemapperInstruction::PayOperationalReward, Match::Anchor))
+        );
+        assert_eq!(
+            lib(&[241, 34, 48, 186, 37, 179, 123, 192]),
+            Some((HivemapperInstruction::LibMintTo, Match::Anchor))
+        );
+        assert_eq!(
+            lib(&[241, 25, 201, 9, 42, 5, 121, 159]),
+            Some((HivemapperInstruction::LibMintTo, Match::FirstByte))
+        );
+
+        // 0xe8 is the lib initialize_global_state, the program 0xe8 instruction isn't in the IDL
+        assert_eq!(program(&[232, 254, 209, 244, 123, 89, 154, 207]), None);
+    }
+
+    #[test]
+    fn lookup_first_byte_only_entries() {
+        assert_eq!(
+            program(&[172, 1, 2, 3, 4, 5, 6, 7]),
+            Some((HivemapperInstruction::PayAndForwardRewardAc, Match::FirstByte))
+        );
+        assert_eq!(lib(&[193]), Some((HivemapperInstruction::LibMapCreate, Match::FirstByte)));
+        assert_eq!(lib(&[108, 0, 0, 0, 0, 0, 0, 0]), Some((HivemapperInstruction::LibMintTo6c, Match::FirstByte)));
+        assert_eq!(program(&[193, 0, 0, 0, 0, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn lookup_falls_back_on_the_first_byte_of_anchor_discriminators() {
+        assert_eq!(
+            program(&[151, 0, 0, 0, 0, 0, 0, 0]),
+            Some((HivemapperInstruction::PayTo, Match::FirstByte))
+        );
+        assert_eq!(program(&[151, 57, 10, 118]), Some((HivemapperInstruction::PayTo, Match::FirstByte)));
+    }
+
+    #[test]
+    fn lookup_unknown() {
+        assert_eq!(program(&[]), None);
+        assert_eq!(program(&[0, 57, 10, 118, 150, 29, 36, 58]), None);
+        assert_eq!(lookup_program(UNKNOWN_PROGRAM, &[151, 57, 10, 118, 150, 29, 36, 58]), None);
+    }
+}
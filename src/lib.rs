@@ -1,37 +1,111 @@
+mod args;
+mod balances;
 mod constants;
+mod db_out;
+mod error;
 mod event;
+mod fleets;
+mod graph_out;
+mod idl;
+mod instructions;
+mod owners;
+mod params;
 mod pb;
+mod periods;
+mod qa_invoices;
+mod supply;
+mod utils;
 
 use std::ops::Div;
-use crate::pb::hivemapper::types::v1::{burn, mint, Burn, InitializedAccount, Instruction, Mint, Transaction, Transactions, Transfer};
+use crate::pb::hivemapper::types::v1::{burn, mint, split_payment, AccountOwnerChanged, BlockHeader, Burn, ClosedAccount, DecodeError, global_state_change, GlobalStateChange, GlobalStateParameter, IdlAccount, IdlArgument, IdlInstruction, InitializedAccount, InnerInstructionSummary, Instruction, Mint, SplitPayment, Transaction, Transactions, Transfer, UnknownInstruction};
 use substreams::errors::Error;
-use substreams_solana::Address;
 use substreams::log;
+use substreams::Hex;
+use substreams::pb::substreams::Clock;
 use substreams_solana::block_view::InstructionView;
 use substreams_solana::pb::sf::solana::r#type::v1::{ConfirmedTransaction, TokenBalance, TransactionStatusMeta};
-use substreams_solana_program_instructions::token_instruction_2022::TokenInstruction;
+use substreams_solana_program_instructions::token_instruction_2022::{AuthorityType, TokenInstruction};
 use pb::sol::transactions::v1::Transactions as solTransactions;
+use crate::args::ANCHOR_DISCRIMINATOR_LEN;
+use crate::idl::{accounts, LibInitializeConsumptionRewardMetaArgs, LibInitializeGlobalStateArgs, LibReinitializeGlobalStateArgs};
+use crate::error::InstructionError;
 use crate::event::{Event, Type};
+use crate::instructions::{HivemapperInstruction, HivemapperProgram, Match};
+use crate::params::Params;
 use crate::pb::hivemapper::types::v1::instruction::Item;
 
 #[substreams::handlers::map]
-pub fn map_outputs(transactions: solTransactions) -> Result<Transactions, Error> {
+pub fn map_outputs(params: String, clock: Clock, transactions: solTransactions) -> Result<Transactions, Error> {
+    let params = Params::parse(&params)?;
     let mut trxs: Vec<Transaction> = vec![];
-    
+    let mut decode_errors: Vec<DecodeError> = vec![];
+
     for confirmed_trx in transactions_owned(transactions) {
+        let hash = bs58::encode(confirmed_trx.hash()).into_string();
         let mut instructions: Vec<Instruction>  = vec![];
-        for instruction in confirmed_trx.compiled_instructions() {
-            process_instruction(&mut instructions, &instruction, );
+        for (top_level_index, instruction) in confirmed_trx.compiled_instructions().enumerate() {
+            // decoded into a scratch buffer so a failing instruction never leaves partial events behind
+            let mut decoded: Vec<Instruction> = vec![];
+            match process_instruction(&mut decoded, &instruction, &params) {
+                Ok(()) => {
+                    if params.strict() {
+                        let unknown = decoded.iter().find_map(|decoded_instruction| match &decoded_instruction.item {
+                            Some(Item::UnknownInstruction(unknown)) => Some(unknown),
+                            _ => None,
+                        });
+                        if let Some(unknown) = unknown {
+                            return Err(anyhow::anyhow!(
+                                "trx_hash {} unknown instruction of program {} with discriminator {}",
+                                hash,
+                                unknown.program_id,
+                                unknown.discriminator,
+                            ));
+                        }
+                    }
+
+                    let discriminator = hivemapper_discriminator(&instruction, &params);
+                    let instruction_name = hivemapper_instruction_name(&instruction, &params);
+                    instructions.extend(decoded.into_iter().map(|mut decoded_instruction| {
+                        decoded_instruction.top_level_index = top_level_index as u32;
+                        decoded_instruction.discriminator = discriminator.clone();
+                        decoded_instruction.instruction_name = instruction_name.clone();
+                        decoded_instruction
+                    }));
+                }
+                Err(err) if params.strict() => {
+                    return Err(anyhow::anyhow!("trx_hash {} decoding instruction: {}", hash, err));
+                }
+                Err(err) => {
+                    log::info!("trx_hash {} decoding instruction: {}", hash, err);
+                    decode_errors.push(DecodeError {
+                        trx_hash: hash.clone(),
+                        program_id: instruction.program_id().to_string(),
+                        discriminator: Hex(discriminator_bytes(instruction.data())).to_string(),
+                        inner_instructions_count: instruction.inner_instructions().count() as u32,
+                        reason: err.to_string(),
+                    });
+                }
+            }
         }
-        
-        let hash = bs58::encode(confirmed_trx.hash()).into_string();
+
         trxs.push(Transaction {
             trx_hash: hash,
-            instructions
+            instructions,
+            slot: clock.number,
+            block_hash: clock.id.clone(),
+            block_timestamp: clock.timestamp.clone(),
         })
     }
 
-    Ok(Transactions{ transactions: trxs })
+    Ok(Transactions {
+        transactions: trxs,
+        decode_errors,
+        block: Some(BlockHeader {
+            slot: clock.number,
+            hash: clock.id,
+            timestamp: clock.timestamp,
+        }),
+    })
 }
 
 /// Iterates over successful transactions in given block and take ownership.
@@ -45,130 +119,121 @@ pub fn transactions_owned(transactions: solTransactions) -> impl Iterator<Item=C
 }
 
 
-pub fn process_instruction(output: &mut Vec<Instruction>, compile_instruction: &InstructionView) {
-    let trx_hash = &bs58::encode(compile_instruction.transaction().hash()).into_string();
-    match compile_instruction.program_id().to_string().as_ref() {
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM => {
-            process_honey_program_instruction(compile_instruction, trx_hash, compile_instruction.meta(), output);
-        }
-        constants::HONEY_TOKEN_INSTRUCTION_LIB => {
-            let instruction_count = compile_instruction.inner_instructions().count();
-            if instruction_count == 0 {
-                return;
-            }
-            if instruction_count != 1 {
-                panic!("expecting 1 instructions trx {}", trx_hash);
-            }
-            process_honey_token_lib(
-                compile_instruction,
-                &compile_instruction.inner_instructions().nth(0).unwrap(),
-                trx_hash,
-                compile_instruction.meta(),
-                output,
-            );
-        }
-        constants::SOLANA_TOKEN_PROGRAM => {
-            match process_token_instruction(compile_instruction, compile_instruction.meta()) {
-                Err(err) => {
-                    panic!("trx_hash {} process token instructions: {}", trx_hash, err);
-                }
-                Ok(ev_option) => {
-                    if let Some(ev) = ev_option {
-                        match ev.r#type {
-                            Type::Mint(mint) => output.push(
-                                Instruction {
-                                    item: Some(Item::Mint(mint)),
-                                }),
-                            Type::Burn(burn) => output.push(Instruction {
-                                item: Some(Item::Burn(burn)),
-                            }),
-                            Type::Transfer(transfer) => {
-                                output.push(Instruction {
-                                    item: Some(Item::Transfer(transfer)),
-                                });
-                            }
-                            Type::InitializeAccount(initialize_account) => {
-                                output.push(Instruction {
-                                    item: Some(Item::InitializedAccount(initialize_account)),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        _ => {
-            process_default_inner_instruction(compile_instruction, trx_hash, compile_instruction.meta(), output);
+pub fn process_instruction(
+    output: &mut Vec<Instruction>,
+    compile_instruction: &InstructionView,
+    params: &Params,
+) -> Result<(), InstructionError> {
+    let program_id = compile_instruction.program_id().to_string();
+    let meta = compile_instruction.meta();
+
+    if program_id == params.program_id {
+        process_honey_program_instruction(compile_instruction, meta, params, output)?;
+    } else if program_id == params.lib_program_id {
+        process_honey_token_lib(compile_instruction, meta, params, output)?;
+    } else if program_id == constants::SOLANA_TOKEN_PROGRAM {
+        if let Some(ev) = process_token_instruction(compile_instruction, meta, params)? {
+            output.push(top_level_output(event_to_item(ev)));
         }
+    } else {
+        process_default_inner_instruction(compile_instruction, meta, params, output)?;
     }
+
+    Ok(())
 }
 
 pub fn process_honey_token_lib(
     instruction: &InstructionView,
-    secondinstruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    if instruction.program_id().to_string().as_str() != constants::HONEY_TOKEN_INSTRUCTION_LIB {
-        panic!("expected instruction of program HONEY_TOKEN_INSTRUCTION_PROGRAM_LIB got {}", instruction.program_id().to_string().as_str())
+) -> Result<(), InstructionError> {
+    if instruction.program_id().to_string() != params.lib_program_id {
+        return Err(InstructionError::UnexpectedProgram {
+            expected: params.lib_program_id.clone(),
+            actual: instruction.program_id().to_string(),
+        });
     }
 
-    match instruction.data()[0] {
-        constants::HONEY_TOKEN_LIB_INITIALIZE_GLOBAL_STATE => {}
-        constants::HONEY_LIB_MAP_CREATE => {
-            process_map_create(secondinstruction, trx_hash, meta, output);
+    let Some(known_instruction) = known_hivemapper_instruction(instruction, params) else {
+        output.push(unknown_instruction_output(instruction));
+        return Ok(());
+    };
+
+    match known_instruction {
+        HivemapperInstruction::LibInitializeGlobalState => {
+            process_initialize_global_state(instruction, output)?;
         }
-        constants::HONEY_LIB_MINT_TO => {
-            process_mint_to(secondinstruction, trx_hash, meta, output);
-            let mint = extract_mint_to_instruction(secondinstruction, trx_hash, meta, mint::Type::Mint);
-            output.push(mint)
+        HivemapperInstruction::LibInitializeConsumptionRewardMeta => {
+            process_initialize_consumption_reward_meta(instruction, output)?;
         }
-        constants::HONEY_LIB_BURN => {
-            let burn = extract_burn_instruction(secondinstruction, trx_hash, meta, burn::Type::Burn);
-            output.push(burn);
+        HivemapperInstruction::LibReinitializeGlobalState => {
+            process_reinitialize_global_state(instruction, output)?;
         }
-        constants::HONEY_LIB_BURN_AND_ADD_ADDITIONAL_HONEY_SUPPLY => {
-            let burn = extract_burn_instruction(secondinstruction, trx_hash, meta, burn::Type::Burn);
-            output.push(burn);
+
+        HivemapperInstruction::LibMapCreate
+        | HivemapperInstruction::LibMintTo
+        | HivemapperInstruction::LibMintTo6c
+        | HivemapperInstruction::LibBurn
+        | HivemapperInstruction::LibBurnAndAddAdditionalHoneySupply => {
+            process_lib_token_instruction(instruction, known_instruction, meta, params, output)?;
+        }
+
+        other => {
+            output.push(idl_instruction_output(instruction, other)?);
         }
+    }
+
+    Ok(())
+}
 
-        constants::HONEY_LIB_INITIALIZE_CONSUMPTION_REWARD_META => {}
+/// Lib instructions moving tokens through a single token program inner instruction.
+fn process_lib_token_instruction(
+    instruction: &InstructionView,
+    lib_instruction: HivemapperInstruction,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let inner_instructions_count = instruction.inner_instructions().count();
+    if inner_instructions_count == 0 {
+        return Ok(());
+    }
+    if inner_instructions_count != 1 {
+        return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
+    }
 
-        constants::HONEY_LIB_REINITIALIZE_GLOBAL_STATE => {}
-        _ => {
-            panic!("instruction program account HONEY_TOKEN_SPLITTING_CONTRACT but found no match trx_hash: {} inst.data: {}", trx_hash, instruction.data()[0]);
+    match lib_instruction {
+        HivemapperInstruction::LibMapCreate => {
+            let burn = extract_burn_instruction(instruction, 0, meta, params, burn::Type::MapCreate)?;
+            output.push(burn);
+        }
+        HivemapperInstruction::LibMintTo | HivemapperInstruction::LibMintTo6c => {
+            process_mint_to(instruction, 0, meta, params, output)?;
+        }
+        HivemapperInstruction::LibBurn | HivemapperInstruction::LibBurnAndAddAdditionalHoneySupply => {
+            let burn = extract_burn_instruction(instruction, 0, meta, params, burn::Type::Burn)?;
+            output.push(burn);
+        }
+        other => {
+            return Err(InstructionError::UnexpectedInstruction(other));
         }
     }
+
+    Ok(())
 }
 
 pub fn process_default_inner_instruction(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    for inner in compile_instruction.inner_instructions() {
+) -> Result<(), InstructionError> {
+    for (inner_index, inner) in compile_instruction.inner_instructions().enumerate() {
         match inner.program_id().to_string().as_ref() {
             constants::SOLANA_TOKEN_PROGRAM => {
-                match process_token_instruction(&inner, meta) {
-                    Err(err) => {
-                        panic!("trx_hash {} process token instructions {}", trx_hash, err);
-                    }
-                    Ok(ev_option) => {
-                        if let Some(ev) = ev_option {
-                            match ev.r#type {
-                                Type::Mint(mint) => output.push(Instruction { item: Some(Item::Mint(mint)) }),
-                                Type::Burn(burn) => output.push(Instruction { item: Some(Item::Burn(burn)) }),
-                                Type::Transfer(transfer) => {
-                                    output.push(Instruction { item: Some(Item::Transfer(transfer)) });
-                                }
-                                Type::InitializeAccount(initialize_account) => {
-                                    output.push(Instruction { item: Some(Item::InitializedAccount(initialize_account)) });
-                                }
-                            }
-                        }
-                    }
+                if let Some(ev) = process_token_instruction(&inner, meta, params)? {
+                    output.push(inner_instruction_output(compile_instruction, inner_index, event_to_item(ev)));
                 }
             }
             _ => {
@@ -176,660 +241,816 @@ pub fn process_default_inner_instruction(
             }
         }
     }
+
+    Ok(())
 }
 
 pub fn process_honey_program_instruction(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    match compile_instruction.data()[0] {
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_TO => {
+) -> Result<(), InstructionError> {
+    let inner_instructions_count = compile_instruction.inner_instructions().count();
+
+    let Some(known_instruction) = known_hivemapper_instruction(compile_instruction, params) else {
+        output.push(unknown_instruction_output(compile_instruction));
+        return Ok(());
+    };
+
+    match known_instruction {
+        HivemapperInstruction::PayTo => {
             let mint_instruction = extract_mint_to_instruction(
-                &compile_instruction.inner_instructions().nth(1).unwrap(),
-                trx_hash,
+                compile_instruction,
+                1,
                 meta,
+                params,
                 mint::Type::RegularDriver,
-            );
+            )?;
             output.push(mint_instruction)
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_CREATE_PAYMENT_INVOICE => {}
-        constants::HONEY_TOKEN_INSTRUCTION_INITIALIZE_DEFAULT_PERIOD => {}
-        constants::HONEY_TOKEN_INSTRUCTION_INITIALIZE_PAYMENT_PERIOD => {
-            if compile_instruction.inner_instructions().count() <= 2 {
-                return; //nothing to do
-            }
-            if compile_instruction.inner_instructions().count() == 3 {
+        HivemapperInstruction::InitializePaymentPeriod => {
+            if inner_instructions_count == 3 {
                 let mint_instruction = extract_mint_to_instruction(
-                    &compile_instruction.inner_instructions().nth(2).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    2,
                     meta,
+                    params,
                     mint::Type::Mint,
-                );
+                )?;
                 output.push(mint_instruction);
-                return;
+            } else if inner_instructions_count > 3 {
+                return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
+            }
+
+            // the period arguments are decoded on their own so a layout mismatch never drops the mint above
+            match idl_instruction_output(compile_instruction, known_instruction) {
+                Ok(period) => output.push(period),
+                Err(err) if params.strict() => return Err(err),
+                Err(err) => {
+                    log::info!(
+                        "trx_hash {} skipping payment period arguments: {}",
+                        bs58::encode(compile_instruction.transaction().hash()).into_string(),
+                        err
+                    );
+                }
             }
-            panic!("expecting lest than 3 instructions got {} trx {}", compile_instruction.inner_instructions().count(), trx_hash)
         }
-        constants::HONEY_TOKEN_INSTRUCTION_UPDATE_MAP_PROGRESS => {}
-        constants::HONEY_TOKEN_INSTRUCTION_CREATE_IMAGERY_QA_INVOICE => {}
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_PAY_IMAGERY_QA_INVOICE => {
-            let mint_instruction = extract_mint_to_instruction(
-                &compile_instruction.inner_instructions().nth(1).unwrap(),
-                trx_hash,
-                meta,
-                mint::Type::AiTrainer,
-            );
-            output.push(mint_instruction)
+        HivemapperInstruction::PayImageryQaInvoice => {
+            process_pay_imagery_qa_invoice(compile_instruction, known_instruction, meta, params, output)?;
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_PAY_OPERATIOANL_REWARD => {
+        HivemapperInstruction::PayOperationalReward => {
             let mint_instruction = extract_mint_to_instruction(
-                &compile_instruction.inner_instructions().nth(1).unwrap(),
-                trx_hash,
+                compile_instruction,
+                1,
                 meta,
+                params,
                 mint::Type::Operational,
-            );
+            )?;
             output.push(mint_instruction)
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_PAY_AND_FORWARD_REWARD_AC => {
-            if compile_instruction.inner_instructions().count() == 4 {
-                process_token_splitting_fleet_ac(compile_instruction, trx_hash, meta, output);
+        HivemapperInstruction::PayAndForwardRewardAc => {
+            if inner_instructions_count == 4 {
+                process_token_splitting_fleet_ac(compile_instruction, known_instruction, meta, params, output)?;
             }
 
-            if compile_instruction.inner_instructions().count() == 2 {
-                process_no_splitting_payments_ac(compile_instruction, trx_hash, meta, output);
+            if inner_instructions_count == 2 {
+                process_no_splitting_payments_ac(compile_instruction, known_instruction, meta, params, output)?;
             }
         }
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_PAY_AND_FORWARD_REWARD_SPLIT_E9 => {
-            if compile_instruction.inner_instructions().count() == 4 {
-                process_token_splitting_fleet_e9(compile_instruction, trx_hash, meta, output);
+        HivemapperInstruction::PayAndForwardReward => {
+            if inner_instructions_count == 4 {
+                process_token_splitting_fleet_e9(compile_instruction, known_instruction, meta, params, output)?;
             }
 
-            if compile_instruction.inner_instructions().count() == 2 {
-                process_no_splitting_payments_e9(compile_instruction, trx_hash, meta, output);
+            if inner_instructions_count == 2 {
+                process_no_splitting_payments_e9(compile_instruction, known_instruction, meta, params, output)?;
             }
         }
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_REMOVE_INVOICE => {}
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_MAP_COMSUMPTION_REWARD => {
+        HivemapperInstruction::PayMapConsumptionReward => {
             let mint_instruction = extract_mint_to_instruction(
-                &compile_instruction.inner_instructions().nth(1).unwrap(),
-                trx_hash,
+                compile_instruction,
+                1,
                 meta,
+                params,
                 mint::Type::MapConsumption,
-            );
+            )?;
             output.push(mint_instruction)
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PROGRAM_PAY_REWARD => {
+        HivemapperInstruction::PayReward => {
             let mint_instruction = extract_mint_to_instruction(
-                &compile_instruction.inner_instructions().nth(1).unwrap(),
-                trx_hash,
+                compile_instruction,
+                1,
                 meta,
+                params,
                 mint::Type::RegularDriver,
-            );
+            )?;
             output.push(mint_instruction)
         }
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_AND_FORWARD_CONSUMPTION_REWARD => {
-            if compile_instruction.inner_instructions().count() == 6 {
-                process_token_splitting_fleet_e9(compile_instruction, trx_hash, meta, output);
+        HivemapperInstruction::PayAndForwardConsumptionReward => {
+            if inner_instructions_count == 6 {
+                process_token_splitting_fleet_e9(compile_instruction, known_instruction, meta, params, output)?;
                 let burn = extract_burn_instruction(
-                    &compile_instruction.inner_instructions().nth(5).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    5,
                     meta,
+                    params,
                     burn::Type::Burn,
-                );
+                )?;
                 output.push(burn);
-                return;
+                return Ok(());
             }
 
-            if compile_instruction.inner_instructions().count() == 4 {
-                let first_instruction = &compile_instruction.inner_instructions().nth(0).unwrap();
-                let third_instruction = &compile_instruction.inner_instructions().nth(2).unwrap();
-
-                if first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO && third_instruction.data()[0] == constants::HONEY_LIB_MINT_TO ||
-                    first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO_6C && third_instruction.data()[0] == constants::HONEY_LIB_MINT_TO_6C {
-                    process_token_splitting_fleet_e9(compile_instruction, trx_hash, meta, output);
-                    return;
-                } else if first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO && third_instruction.data()[0] == constants::HONEY_LIB_BURN ||
-                    first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO_6C && third_instruction.data()[0] == constants::HONEY_LIB_BURN {
-                    process_no_splitting_payments_e9(compile_instruction, trx_hash, meta, output);
+            if inner_instructions_count == 4 {
+                // both lib calls are followed by the token instruction they make, paired on those so lib
+                // instructions missing from the IDL are handled as well
+                let first = token_instruction_kind(compile_instruction, 1, meta, params)?;
+                let second = token_instruction_kind(compile_instruction, 3, meta, params)?;
+
+                if first == "mint" && second == "mint" {
+                    process_token_splitting_fleet_e9(compile_instruction, known_instruction, meta, params, output)?;
+                    return Ok(());
+                } else if first == "mint" && second == "burn" {
+                    process_no_splitting_payments_e9(compile_instruction, known_instruction, meta, params, output)?;
                     let burn = extract_burn_instruction(
-                        &compile_instruction.inner_instructions().nth(3).unwrap(),
-                        trx_hash,
+                        compile_instruction,
+                        3,
                         meta,
+                        params,
                         burn::Type::Burn,
-                    );
+                    )?;
                     output.push(burn);
-                    return;
+                    return Ok(());
                 } else {
-                    panic!("unknown instruction pairing trx {}", trx_hash);
+                    return Err(InstructionError::UnknownInstructionPairing { first, second });
                 }
             }
 
-            if compile_instruction.inner_instructions().count() == 2 {
-                process_no_splitting_payments_e9(compile_instruction, trx_hash, meta, output);
-                return;
+            if inner_instructions_count == 2 {
+                process_no_splitting_payments_e9(compile_instruction, known_instruction, meta, params, output)?;
+                return Ok(());
             }
 
-            panic!("expecting 2 or 4 or 6 instructions got {} trx {}", compile_instruction.inner_instructions().count(), trx_hash)
+            return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_CONSUMPTION_REWARD => {
-            if compile_instruction.inner_instructions().count() == 4 {
+        HivemapperInstruction::PayConsumptionReward => {
+            if inner_instructions_count == 4 {
                 let mint_instruction = extract_mint_to_instruction(
-                    &compile_instruction.inner_instructions().nth(1).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    1,
                     meta,
+                    params,
                     mint::Type::MapConsumption,
-                );
+                )?;
                 output.push(mint_instruction);
 
 
                 let burn = extract_burn_instruction(
-                    &compile_instruction.inner_instructions().nth(3).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    3,
                     meta,
+                    params,
                     burn::Type::Burn,
-                );
+                )?;
                 output.push(burn);
-                return;
+                return Ok(());
             }
-            if compile_instruction.inner_instructions().count() == 2 {
+            if inner_instructions_count == 2 {
                 let mint_instruction = extract_mint_to_instruction(
-                    &compile_instruction.inner_instructions().nth(1).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    1,
                     meta,
+                    params,
                     mint::Type::MapConsumption,
-                );
+                )?;
                 output.push(mint_instruction);
-                return;
+                return Ok(());
             }
-            panic!("expecting 2 or 4 instructions got {} trx {}", compile_instruction.inner_instructions().count(), trx_hash)
+            return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_BURST_REWARD => {
-            if compile_instruction.inner_instructions().count() == 4 {
+        HivemapperInstruction::PayBurstReward => {
+            if inner_instructions_count == 4 {
                 let mint_instruction = extract_mint_to_instruction(
-                    &compile_instruction.inner_instructions().nth(1).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    1,
                     meta,
+                    params,
                     mint::Type::RegularDriver,
-                );
+                )?;
                 output.push(mint_instruction);
 
                 let burn = extract_burn_instruction(
-                    &compile_instruction.inner_instructions().nth(3).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    3,
                     meta,
+                    params,
                     burn::Type::Burn,
-                );
+                )?;
                 output.push(burn);
-                return;
+                return Ok(());
             }
-            panic!("expecting 4 instructions got {} trx {}", compile_instruction.inner_instructions().count(), trx_hash)
+            return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
         }
 
-        constants::HONEY_TOKEN_INSTRUCTION_PAY_AND_FORWARD_BURST_REWARD => {
-            if compile_instruction.inner_instructions().count() == 6 {
-                process_token_splitting_fleet_e9(compile_instruction, trx_hash, meta, output);
+        HivemapperInstruction::PayAndForwardBurstReward => {
+            if inner_instructions_count == 6 {
+                process_token_splitting_fleet_e9(compile_instruction, known_instruction, meta, params, output)?;
                 let burn = extract_burn_instruction(
-                    &compile_instruction.inner_instructions().nth(5).unwrap(),
-                    trx_hash,
+                    compile_instruction,
+                    5,
                     meta,
+                    params,
                     burn::Type::Burn,
-                );
+                )?;
                 output.push(burn);
-                return;
+                return Ok(());
             }
 
-            if compile_instruction.inner_instructions().count() == 4 {
-                let first_instruction = &compile_instruction.inner_instructions().nth(0).unwrap();
-                let third_instruction = &compile_instruction.inner_instructions().nth(2).unwrap();
-
-                if first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO && third_instruction.data()[0] == constants::HONEY_LIB_MINT_TO {
-                    process_token_splitting_fleet_e9(compile_instruction, trx_hash, meta, output);
-                    return;
-                } else if first_instruction.data()[0] == constants::HONEY_LIB_MINT_TO && third_instruction.data()[0] == constants::HONEY_LIB_BURN {
-                    process_no_splitting_payments_e9(compile_instruction, trx_hash, meta, output);
+            if inner_instructions_count == 4 {
+                // both lib calls are followed by the token instruction they make, paired on those so lib
+                // instructions missing from the IDL are handled as well
+                let first = token_instruction_kind(compile_instruction, 1, meta, params)?;
+                let second = token_instruction_kind(compile_instruction, 3, meta, params)?;
+
+                if first == "mint" && second == "mint" {
+                    process_token_splitting_fleet_e9(compile_instruction, known_instruction, meta, params, output)?;
+                    return Ok(());
+                } else if first == "mint" && second == "burn" {
+                    process_no_splitting_payments_e9(compile_instruction, known_instruction, meta, params, output)?;
                     let burn = extract_burn_instruction(
-                        &compile_instruction.inner_instructions().nth(3).unwrap(),
-                        trx_hash,
+                        compile_instruction,
+                        3,
                         meta,
+                        params,
                         burn::Type::Burn,
-                    );
+                    )?;
                     output.push(burn);
-                    return;
+                    return Ok(());
                 } else {
-                    panic!("unknown instruction pairing");
+                    return Err(InstructionError::UnknownInstructionPairing { first, second });
                 }
             }
 
-            if compile_instruction.inner_instructions().count() == 2 {
-                process_no_splitting_payments_e9(compile_instruction, trx_hash,  meta, output);
-                return;
+            if inner_instructions_count == 2 {
+                process_no_splitting_payments_e9(compile_instruction, known_instruction, meta, params, output)?;
+                return Ok(());
             }
 
-            panic!("expecting 2 or 4 or 6 instructions got {} trx {}", compile_instruction.inner_instructions().count(), trx_hash)
+            return Err(InstructionError::UnexpectedInnerInstructionsCount(inner_instructions_count));
         }
 
-        _ => {
-            panic!("instruction program account HONEY_TOKEN_INSTRUCTION_PROGRAM but found no match trx_hash: {} inst.data: {}", trx_hash, compile_instruction.data()[0]);
+        other => {
+            output.push(idl_instruction_output(compile_instruction, other)?);
         }
     }
+
+    Ok(())
 }
 
 
 
+pub fn process_pay_imagery_qa_invoice(
+    compile_instruction: &InstructionView,
+    hivemapper_instruction: HivemapperInstruction,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let mint = extract_mint_to(&inner_instruction(compile_instruction, 1)?, meta, params, mint::Type::AiTrainer)?;
+
+    output.push(idl_instruction_output(compile_instruction, hivemapper_instruction)?);
+    output.push(inner_instruction_output(compile_instruction, 1, Item::Mint(mint)));
+
+    Ok(())
+}
+
+pub fn process_initialize_global_state(
+    instruction: &InstructionView,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let LibInitializeGlobalStateArgs { credit_to_honey_rate, additional_honey_supply } =
+        LibInitializeGlobalStateArgs::decode(instruction.data())?;
+
+    output.push(top_level_output(Item::GlobalStateChange(GlobalStateChange {
+        action: global_state_change::Action::InitializeGlobalState.into(),
+        authority: account_at(instruction, accounts::lib_initialize_global_state::AUTHORITY)?,
+        account: account_at(instruction, accounts::lib_initialize_global_state::GLOBAL_STATE)?,
+        parameters: vec![
+            global_state_parameter("credit_to_honey_rate", credit_to_honey_rate),
+            global_state_parameter("additional_honey_supply", additional_honey_supply),
+        ],
+    })));
+
+    Ok(())
+}
+
+pub fn process_reinitialize_global_state(
+    instruction: &InstructionView,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let LibReinitializeGlobalStateArgs { credit_to_honey_rate, additional_honey_supply } =
+        LibReinitializeGlobalStateArgs::decode(instruction.data())?;
+
+    output.push(top_level_output(Item::GlobalStateChange(GlobalStateChange {
+        action: global_state_change::Action::ReinitializeGlobalState.into(),
+        authority: account_at(instruction, accounts::lib_reinitialize_global_state::AUTHORITY)?,
+        account: account_at(instruction, accounts::lib_reinitialize_global_state::GLOBAL_STATE)?,
+        parameters: vec![
+            global_state_parameter("credit_to_honey_rate", credit_to_honey_rate),
+            global_state_parameter("additional_honey_supply", additional_honey_supply),
+        ],
+    })));
+
+    Ok(())
+}
+
+pub fn process_initialize_consumption_reward_meta(
+    instruction: &InstructionView,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let LibInitializeConsumptionRewardMetaArgs { consumption_reward_rate } =
+        LibInitializeConsumptionRewardMetaArgs::decode(instruction.data())?;
+
+    output.push(top_level_output(Item::GlobalStateChange(GlobalStateChange {
+        action: global_state_change::Action::InitializeConsumptionRewardMeta.into(),
+        authority: account_at(instruction, accounts::lib_initialize_consumption_reward_meta::AUTHORITY)?,
+        account: account_at(instruction, accounts::lib_initialize_consumption_reward_meta::CONSUMPTION_REWARD_META)?,
+        parameters: vec![global_state_parameter("consumption_reward_rate", consumption_reward_rate)],
+    })));
+
+    Ok(())
+}
+
+fn global_state_parameter(name: &str, value: u64) -> GlobalStateParameter {
+    GlobalStateParameter {
+        name: name.to_string(),
+        value,
+    }
+}
+
 fn extract_mint_to_instruction(
-    mint_instruction: &InstructionView,
-    trx_hash: &String,
+    compile_instruction: &InstructionView,
+    inner_index: usize,
     meta: &TransactionStatusMeta,
+    params: &Params,
     t: mint::Type,
-) -> Instruction {
-    let mint = extract_mint_to(mint_instruction, trx_hash, meta, t);
-    Instruction {
-        item: Some(Item::Mint(mint)),
-    }
+) -> Result<Instruction, InstructionError> {
+    let mint = extract_mint_to(&inner_instruction(compile_instruction, inner_index)?, meta, params, t)?;
+    Ok(inner_instruction_output(compile_instruction, inner_index, Item::Mint(mint)))
 }
 fn extract_mint_to(
     mint_instruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
     t: mint::Type,
-) -> Mint {
-    match crate::process_token_instruction(&mint_instruction, meta) {
-        Err(err) => {
-            panic!("trx_hash {} token splitting fleet: {}", trx_hash, err);
-        }
-        Ok(ev_option) => {
-            if let Some(ev) = ev_option {
-                match ev.r#type {
-                    Type::Mint(mut mint) => {
-                        mint.r#type = t.into();
-                        return mint;
-                    }
-                    _ => {
-                        panic!("expecting only mint trx {}", trx_hash)
-                    }
-                }
-            }
+) -> Result<Mint, InstructionError> {
+    match process_token_instruction(&mint_instruction, meta, params)? {
+        Some(Event { r#type: Type::Mint(mut mint) }) => {
+            mint.r#type = t.into();
+            Ok(mint)
         }
+        _ => Err(InstructionError::UnexpectedTokenInstruction("mint")),
     }
-    panic!("no mint found")
 }
 
 fn extract_burn_instruction(
-    burn_instruction: &InstructionView,
-    trx_hash: &String,
+    compile_instruction: &InstructionView,
+    inner_index: usize,
     meta: &TransactionStatusMeta,
+    params: &Params,
     t: burn::Type,
-) -> Instruction {
-    let burn = extract_burn(burn_instruction, trx_hash, meta, t);
-    Instruction {
-        item: Some(Item::Burn(burn)),
-    }
+) -> Result<Instruction, InstructionError> {
+    let burn = extract_burn(&inner_instruction(compile_instruction, inner_index)?, meta, params, t)?;
+    Ok(inner_instruction_output(compile_instruction, inner_index, Item::Burn(burn)))
 }
 
 fn extract_burn(
     burn_instruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
     t: burn::Type,
-) -> Burn {
-    match crate::process_token_instruction(&burn_instruction, meta) {
-        Err(err) => {
-            panic!("trx_hash {} token splitting fleet: {} trx {}", trx_hash, err, trx_hash);
-        }
-        Ok(ev_option) => {
-            if let Some(ev) = ev_option {
-                match ev.r#type {
-                    Type::Burn(mut burn) => {
-                        burn.r#type = t.into();
-                        return burn;
-                    }
-                    _ => {
-                        panic!("expecting only mint trx {}", trx_hash)
-                    }
-                }
-            }
+) -> Result<Burn, InstructionError> {
+    match process_token_instruction(&burn_instruction, meta, params)? {
+        Some(Event { r#type: Type::Burn(mut burn) }) => {
+            burn.r#type = t.into();
+            Ok(burn)
         }
+        _ => Err(InstructionError::UnexpectedTokenInstruction("burn")),
     }
-    panic!("no burn found, trx_hash trx {}", trx_hash)
 }
 
 
-pub fn process_map_create(
-    instruction: &InstructionView,
-    trx_hash: &String,
-    meta: &TransactionStatusMeta,
-    output: &mut Vec<Instruction>,
-) {
-    let burn = extract_burn(&instruction, trx_hash, meta, burn::Type::MapCreate);
-    output.push(Instruction {
-        item: Some(Item::Burn(burn)),
-    });
-
-    return;
-}
 pub fn process_mint_to(
-    instruction: &InstructionView,
-    trx_hash: &String,
+    compile_instruction: &InstructionView,
+    inner_index: usize,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    let mint = extract_mint_to_instruction(&instruction, trx_hash, meta, mint::Type::Mint);
+) -> Result<(), InstructionError> {
+    let mint = extract_mint_to_instruction(compile_instruction, inner_index, meta, params, mint::Type::Mint)?;
     output.push(mint);
 
-    return;
+    Ok(())
 }
 
 pub fn process_token_splitting_fleet_ac(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
+    hivemapper_instruction: HivemapperInstruction,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    let fleet_driver_account = &compile_instruction.accounts()[3];
-    let fleet_account = &compile_instruction.accounts()[4];
+) -> Result<(), InstructionError> {
+    let (fleet_driver_account, fleet_account) = fleet_accounts(compile_instruction, hivemapper_instruction)?;
+
+    process_token_splitting_fleet(
+        compile_instruction,
+        meta,
+        params,
+        split_payment::Variant::Ac,
+        fleet_account,
+        fleet_driver_account,
+        output,
+    )
+}
 
-    let mut manager_mint = None;
-    let mut driver_mint = None;
+pub fn process_token_splitting_fleet_e9(
+    compile_instruction: &InstructionView,
+    hivemapper_instruction: HivemapperInstruction,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let (fleet_driver_account, fleet_account) = fleet_accounts(compile_instruction, hivemapper_instruction)?;
+
+    process_token_splitting_fleet(
+        compile_instruction,
+        meta,
+        params,
+        split_payment::Variant::E9,
+        fleet_account,
+        fleet_driver_account,
+        output,
+    )
+}
 
-    for inner_instruction in compile_instruction.inner_instructions() {
-        if inner_instruction.program_id().to_string().as_str() != constants::SOLANA_TOKEN_PROGRAM {
-            continue;
+/// Fleet driver and fleet manager accounts of a PAY_AND_FORWARD_* instruction, from its IDL account roles.
+fn fleet_accounts(
+    compile_instruction: &InstructionView,
+    hivemapper_instruction: HivemapperInstruction,
+) -> Result<(String, String), InstructionError> {
+    let (driver, fleet) = match hivemapper_instruction {
+        HivemapperInstruction::PayAndForwardRewardAc => {
+            (accounts::pay_and_forward_reward_ac::DRIVER, accounts::pay_and_forward_reward_ac::FLEET)
         }
-        match process_token_instruction(&inner_instruction, meta) {
-            Err(err) => {
-                panic!("trx_hash {} token splitting fleet: {}", trx_hash, err);
-            }
-            Ok(ev_option) => {
-                if let Some(ev) = ev_option {
-                    match ev.r#type {
-                        Type::Mint(mut mint) => {
-                            if mint.to.eq(&fleet_account.to_string()) {
-                                mint.r#type = mint::Type::FleetManager.into();
-                                manager_mint = Some(mint);
-                            } else if mint.to.eq(&fleet_driver_account.to_string()) {
-                                mint.r#type = mint::Type::FleetDriver.into();
-                                driver_mint = Some(mint);
-                            } else {
-                                panic!("mint not found! for driver or fleet trx {}", trx_hash);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
+        HivemapperInstruction::PayAndForwardReward => {
+            (accounts::pay_and_forward_reward::DRIVER, accounts::pay_and_forward_reward::FLEET)
         }
-    }
-    if manager_mint.is_some() && driver_mint.is_some() {
-        output.push(Instruction {
-            item: Some(Item::Mint(manager_mint.unwrap())),
-        });
+        HivemapperInstruction::PayAndForwardConsumptionReward => (
+            accounts::pay_and_forward_consumption_reward::DRIVER,
+            accounts::pay_and_forward_consumption_reward::FLEET,
+        ),
+        HivemapperInstruction::PayAndForwardBurstReward => (
+            accounts::pay_and_forward_burst_reward::DRIVER,
+            accounts::pay_and_forward_burst_reward::FLEET,
+        ),
+        other => return Err(InstructionError::UnexpectedInstruction(other)),
+    };
 
-        output.push(Instruction {
-            item: Some(Item::Mint(driver_mint.unwrap())),
-        });
-    } else {
-        panic!("Missing a mints {} {} trx {}", manager_mint.is_some(), driver_mint.is_some(), trx_hash);
-    }
+    Ok((account_at(compile_instruction, driver)?, account_at(compile_instruction, fleet)?))
 }
-pub fn process_token_splitting_fleet_e9(
+
+fn process_token_splitting_fleet(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
     meta: &TransactionStatusMeta,
+    params: &Params,
+    variant: split_payment::Variant,
+    fleet_account: String,
+    fleet_driver_account: String,
     output: &mut Vec<Instruction>,
-) {
-    let fleet_driver_account = &compile_instruction.accounts()[4];
-    let fleet_account = &compile_instruction.accounts()[5];
-
+) -> Result<(), InstructionError> {
     let mut manager_mint = None;
     let mut driver_mint = None;
 
-    for inner_instruction in compile_instruction.inner_instructions() {
+    for (inner_index, inner_instruction) in compile_instruction.inner_instructions().enumerate() {
         if inner_instruction.program_id().to_string().as_str() != constants::SOLANA_TOKEN_PROGRAM {
             continue;
         }
-        match process_token_instruction(&inner_instruction, meta) {
-            Err(err) => {
-                panic!("trx_hash {} token splitting fleet: {}", trx_hash, err);
-            }
-            Ok(ev_option) => {
-                if let Some(ev) = ev_option {
-                    match ev.r#type {
-                        Type::Mint(mint) => {
-                            if mint.to.eq(&fleet_account.to_string()) {
-                                manager_mint = Some(mint);
-                            } else if mint.to.eq(&fleet_driver_account.to_string()) {
-                                driver_mint = Some(mint);
-                            } else {
-                                for a in compile_instruction.accounts() {
-                                    log::info!("{}", a.to_string())
-                                }
-                                // return;
-                                panic!("mint not found! for driver or fleet trx {}, mint to {} fleet {} driver {}", trx_hash, mint.to, fleet_account.to_string(), fleet_driver_account.to_string());
-                            }
-                        }
-                        _ => {}
-                    }
-                }
+        if let Some(Event { r#type: Type::Mint(mut mint) }) = process_token_instruction(&inner_instruction, meta, params)? {
+            if mint.to.eq(&fleet_account) {
+                mint.r#type = mint::Type::FleetManager.into();
+                manager_mint = Some((inner_index, mint));
+            } else if mint.to.eq(&fleet_driver_account) {
+                mint.r#type = mint::Type::FleetDriver.into();
+                driver_mint = Some((inner_index, mint));
+            } else {
+                return Err(InstructionError::UnmatchedFleetMint {
+                    to: mint.to,
+                    fleet: fleet_account,
+                    driver: fleet_driver_account,
+                });
             }
         }
     }
-    if manager_mint.is_some() && driver_mint.is_some() {
-        output.push(
-            Instruction {
-                item: Some(Item::Mint(manager_mint.unwrap())),
-            }
-        );
-        output.push(
-            Instruction {
-                item: Some(Item::Mint(driver_mint.unwrap())),
-            }
-        );
-    } else {
-        panic!("Missing a mints {} {} trx {}", manager_mint.is_some(), driver_mint.is_some(), trx_hash);
+
+    match (manager_mint, driver_mint) {
+        (Some((manager_index, manager_mint)), Some((driver_index, driver_mint))) => {
+            let split_payment = split_payment_output(compile_instruction, params, variant, &manager_mint, &driver_mint)?;
+            output.push(inner_instruction_output(compile_instruction, manager_index, Item::Mint(manager_mint)));
+            output.push(inner_instruction_output(compile_instruction, driver_index, Item::Mint(driver_mint)));
+            output.push(split_payment);
+            Ok(())
+        }
+        (manager_mint, driver_mint) => Err(InstructionError::MissingFleetMints {
+            manager: manager_mint.is_some(),
+            driver: driver_mint.is_some(),
+        }),
     }
 }
 
 pub fn process_no_splitting_payments_ac(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
+    hivemapper_instruction: HivemapperInstruction,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    let driver_account = &compile_instruction.accounts()[3];
-    let manager_account = &compile_instruction.accounts()[4];
-
-    let instruction = compile_instruction.inner_instructions().nth(1).unwrap();
-    let mut mint = extract_mint_to(&instruction, trx_hash, meta, mint::Type::Unset);
-
-    let mut manager_mint = Mint {
-        to: manager_account.to_string(),
-        amount: 0.0,
-        r#type: mint::Type::FleetManager.into(),
-    };
-
-    let mut driver_mint = Mint {
-        to: driver_account.to_string(),
-        amount: 0.0,
-        r#type: mint::Type::FleetDriver.into(),
-    };
-
-    if mint.to.eq(&manager_account.to_string()) {
-        manager_mint = mint;
-    } else if mint.to.eq(&driver_account.to_string()) {
-        driver_mint = mint;
-    } else {
-        panic!("mint not found! for driver or fleet trx {}", trx_hash);
-    }
-
-    output.push(
-        Instruction {
-            item: Some(Item::Mint(manager_mint)),
-        }
-    );
-    output.push(
-        Instruction {
-            item: Some(Item::Mint(driver_mint)),
-        }
-    );
+) -> Result<(), InstructionError> {
+    let (driver_account, manager_account) = fleet_accounts(compile_instruction, hivemapper_instruction)?;
+
+    process_no_splitting_payments(
+        compile_instruction,
+        meta,
+        params,
+        split_payment::Variant::Ac,
+        manager_account,
+        driver_account,
+        output,
+    )
 }
 
 pub fn process_no_splitting_payments_e9(
     compile_instruction: &InstructionView,
-    trx_hash: &String,
+    hivemapper_instruction: HivemapperInstruction,
     meta: &TransactionStatusMeta,
+    params: &Params,
     output: &mut Vec<Instruction>,
-) {
-    let driver_account = &compile_instruction.accounts()[4];
-    let manager_account = &compile_instruction.accounts()[5];
+) -> Result<(), InstructionError> {
+    let (driver_account, manager_account) = fleet_accounts(compile_instruction, hivemapper_instruction)?;
+
+    process_no_splitting_payments(
+        compile_instruction,
+        meta,
+        params,
+        split_payment::Variant::E9,
+        manager_account,
+        driver_account,
+        output,
+    )
+}
 
-    let mint_instruction = compile_instruction.inner_instructions().nth(1).unwrap();
-    let mint = extract_mint_to(&mint_instruction, trx_hash, meta, mint::Type::Unset);
+fn process_no_splitting_payments(
+    compile_instruction: &InstructionView,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+    variant: split_payment::Variant,
+    manager_account: String,
+    driver_account: String,
+    output: &mut Vec<Instruction>,
+) -> Result<(), InstructionError> {
+    let mint_instruction = inner_instruction(compile_instruction, 1)?;
+    let mut mint = extract_mint_to(&mint_instruction, meta, params, mint::Type::Unset)?;
 
+    // the side without a mint is reported with a zero amount in the split payment
     let mut manager_mint = Mint {
-        to: manager_account.to_string(),
+        to: manager_account.clone(),
+        to_owner: token_account_owner(compile_instruction, meta, params, &manager_account),
         amount: 0.0,
+        amount_raw: 0,
+        amount_decimal: amount_to_decimal_string(0, params.decimals),
         r#type: mint::Type::FleetManager.into(),
     };
     let mut driver_mint = Mint {
-        to: driver_account.to_string(),
+        to: driver_account.clone(),
+        to_owner: token_account_owner(compile_instruction, meta, params, &driver_account),
         amount: 0.0,
+        amount_raw: 0,
+        amount_decimal: amount_to_decimal_string(0, params.decimals),
         r#type: mint::Type::FleetDriver.into(),
     };
 
-    if mint.to.eq(&manager_account.to_string()) {
-        manager_mint = mint;
-    } else if mint.to.eq(&driver_account.to_string()) {
-        driver_mint = mint;
+    if mint.to.eq(&manager_account) {
+        mint.r#type = mint::Type::FleetManager.into();
+        manager_mint = mint.clone();
+    } else if mint.to.eq(&driver_account) {
+        mint.r#type = mint::Type::FleetDriver.into();
+        driver_mint = mint.clone();
     } else {
-        panic!("mint not found! for driver or fleet trx {}, mint to {} fleet {} driver {}", trx_hash, mint.to, manager_account.to_string(), driver_account.to_string());
+        return Err(InstructionError::UnmatchedFleetMint {
+            to: mint.to,
+            fleet: manager_account,
+            driver: driver_account,
+        });
     }
 
-    output.push(
-        Instruction {
-            item: Some(Item::Mint(manager_mint)),
-        }
-    );
-    output.push(
-        Instruction {
-            item: Some(Item::Mint(driver_mint)),
-        }
-    );
+    let split_payment = split_payment_output(compile_instruction, params, variant, &manager_mint, &driver_mint)?;
+    output.push(inner_instruction_output(compile_instruction, 1, Item::Mint(mint)));
+    output.push(split_payment);
+
+    Ok(())
+}
+
+fn split_payment_output(
+    compile_instruction: &InstructionView,
+    params: &Params,
+    variant: split_payment::Variant,
+    manager_mint: &Mint,
+    driver_mint: &Mint,
+) -> Result<Instruction, InstructionError> {
+    Ok(top_level_output(Item::SplitPayment(SplitPayment {
+        trx_hash: bs58::encode(compile_instruction.transaction().hash()).into_string(),
+        fleet: manager_mint.to.clone(),
+        fleet_owner: manager_mint.to_owner.clone(),
+        fleet_amount: manager_mint.amount,
+        fleet_amount_raw: manager_mint.amount_raw,
+        fleet_amount_decimal: manager_mint.amount_decimal.clone(),
+        driver: driver_mint.to.clone(),
+        driver_owner: driver_mint.to_owner.clone(),
+        driver_amount: driver_mint.amount,
+        driver_amount_raw: driver_mint.amount_raw,
+        driver_amount_decimal: driver_mint.amount_decimal.clone(),
+        variant: variant.into(),
+        discriminator: hivemapper_discriminator(compile_instruction, params),
+    })))
 }
 
 
 pub fn process_token_instruction(
     instruction: &InstructionView,
     meta: &TransactionStatusMeta,
-) -> Result<Option<Event>, Error> {
+    params: &Params,
+) -> Result<Option<Event>, InstructionError> {
     match TokenInstruction::unpack(&instruction.data()) {
         Err(err) => {
-            return Err(anyhow::anyhow!("unpacking token instruction: {}", err));
+            return Err(InstructionError::TokenInstruction(err.to_string()));
         }
         Ok(token_instruction) => match token_instruction {
             TokenInstruction::Transfer { amount: amt } => {
-                let authority = &instruction.accounts()[2];
-
-                // let authority = &accounts[inst_accounts[2] as usize];
-                if is_honey_token_transfer(&meta.pre_token_balances, authority) {
-                    let source = &instruction.accounts()[0];
-                    // let source = &accounts[inst_accounts[0] as usize];
-                    let destination = &instruction.accounts()[1];
-                    // let destination = &accounts[inst_accounts[1] as usize];
+                // Transfer does not reference the mint, HONEY transfers are identified from the source account
+                // token balances, post balances covering a source created within the transaction
+                let source = account_at(instruction, 0)?;
+                let honey_source = honey_token_balance(instruction, params, &meta.pre_token_balances, &source)
+                    .or_else(|| honey_token_balance(instruction, params, &meta.post_token_balances, &source))
+                    .is_some();
+
+                if honey_source {
+                    let destination = account_at(instruction, 1)?;
                     return Ok(Some(Event {
-                        r#type: (Type::Transfer(crate::pb::hivemapper::types::v1::Transfer {
-                            from: source.to_string(),
-                            to: destination.to_string(),
-                            amount: amount_to_decimals(amt as f64, constants::HONEY_TOKEN_DECIMALS as f64),
+                        r#type: (Type::Transfer(Transfer {
+                            from_owner: token_account_owner(instruction, meta, params, &source),
+                            to_owner: token_account_owner(instruction, meta, params, &destination),
+                            from: source,
+                            to: destination,
+                            amount: amount_to_decimals(amt as f64, params.decimals as f64),
+                            amount_raw: amt,
+                            amount_decimal: amount_to_decimal_string(amt, params.decimals),
                         })),
                     }));
                 }
             }
             TokenInstruction::TransferChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[1];
-                // let mint = &accounts[inst_accounts[1] as usize];
-                if mint.to_string() == constants::HONEY_CONTRACT_ADDRESS {
-                    let source = &instruction.accounts()[0];
-                    // let source = &accounts[inst_accounts[0] as usize];
-                    let destination = &instruction.accounts()[2];
-                    // let destination = &accounts[inst_accounts[2] as usize];
+                let mint = account_at(instruction, 1)?;
+                if mint == params.mint {
+                    let source = account_at(instruction, 0)?;
+                    let destination = account_at(instruction, 2)?;
                     return Ok(Some(Event {
-                        r#type: (Type::Transfer(crate::pb::hivemapper::types::v1::Transfer {
-                            from: source.to_string(),
-                            to: destination.to_string(),
-                            amount: amount_to_decimals(amt as f64, constants::HONEY_TOKEN_DECIMALS as f64),
+                        r#type: (Type::Transfer(Transfer {
+                            from_owner: token_account_owner(instruction, meta, params, &source),
+                            to_owner: token_account_owner(instruction, meta, params, &destination),
+                            from: source,
+                            to: destination,
+                            amount: amount_to_decimals(amt as f64, params.decimals as f64),
+                            amount_raw: amt,
+                            amount_decimal: amount_to_decimal_string(amt, params.decimals),
                         })),
                     }));
                 }
             }
             TokenInstruction::MintTo { amount: amt } | TokenInstruction::MintToChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[0];
-                if mint.to_string().as_str() != constants::HONEY_CONTRACT_ADDRESS {
+                let mint = account_at(instruction, 0)?;
+                if mint != params.mint {
                     return Ok(None);
                 }
 
-                let account_to = &instruction.accounts()[1];
+                let account_to = account_at(instruction, 1)?;
                 return Ok(Some(Event {
                     r#type: (Type::Mint(Mint {
-                        to: account_to.to_string(),
-                        amount: amount_to_decimals(amt as f64, constants::HONEY_TOKEN_DECIMALS as f64),
+                        to_owner: token_account_owner(instruction, meta, params, &account_to),
+                        to: account_to,
+                        amount: amount_to_decimals(amt as f64, params.decimals as f64),
+                        amount_raw: amt,
+                        amount_decimal: amount_to_decimal_string(amt, params.decimals),
                         r#type: mint::Type::Mint.into(),
                     })),
                 }));
             }
             TokenInstruction::Burn { amount: amt } | TokenInstruction::BurnChecked { amount: amt, .. } => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != constants::HONEY_CONTRACT_ADDRESS {
+                let mint = account_at(instruction, 1)?;
+                if mint != params.mint {
                     return Ok(None);
                 }
 
-                let account_from = &instruction.accounts()[0];
+                let account_from = account_at(instruction, 0)?;
+                let from_owner = token_account_owner(instruction, meta, params, &account_from);
                 return Ok(Some(Event {
                     r#type: (Type::Burn(Burn {
-                        from: account_from.to_string(),
-                        amount: amount_to_decimals(amt as f64, constants::HONEY_TOKEN_DECIMALS as f64),
+                        from: account_from,
+                        from_owner,
+                        authority: account_at(instruction, 2)?,
+                        amount: amount_to_decimals(amt as f64, params.decimals as f64),
+                        amount_raw: amt,
+                        amount_decimal: amount_to_decimal_string(amt, params.decimals),
                         r#type: burn::Type::Burn.into(),
                     })),
                 }));
             }
             TokenInstruction::InitializeAccount {} => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != constants::HONEY_CONTRACT_ADDRESS {
+                let mint = account_at(instruction, 1)?;
+                if mint != params.mint {
                     return Ok(None);
                 }
 
-                let account = &instruction.accounts()[0];
-                let owner = &instruction.accounts()[2];
+                let account = account_at(instruction, 0)?;
+                let owner = account_at(instruction, 2)?;
                 return Ok(Some(Event {
                     r#type: (Type::InitializeAccount(InitializedAccount {
-                        account: account.to_string(),
-                        mint: mint.to_string(),
-                        owner: owner.to_string(),
+                        account,
+                        mint,
+                        owner,
                     })),
                 }));
             }
             TokenInstruction::InitializeAccount2 { owner: ow } | TokenInstruction::InitializeAccount3 { owner: ow } => {
-                let mint = &instruction.accounts()[1];
-                if mint.to_string().as_str() != constants::HONEY_CONTRACT_ADDRESS {
+                let mint = account_at(instruction, 1)?;
+                if mint != params.mint {
                     return Ok(None);
                 }
 
-                let account = &instruction.accounts()[0];
+                let account = account_at(instruction, 0)?;
                 return Ok(Some(Event {
                     r#type: (Type::InitializeAccount(InitializedAccount {
-                        account: account.to_string(),
-                        mint: mint.to_string(),
+                        account,
+                        mint,
                         owner: bs58::encode(ow).into_string(),
                     })),
                 }));
             }
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::AccountOwner,
+                new_authority,
+            } => {
+                // SetAuthority does not reference the mint, HONEY accounts are identified from the token balances
+                let account = account_at(instruction, 0)?;
+                if honey_token_balance(instruction, params, &meta.post_token_balances, &account).is_none() {
+                    return Ok(None);
+                }
+
+                let new_owner: Option<_> = new_authority.into();
+                let pre_balance = honey_token_balance(instruction, params, &meta.pre_token_balances, &account);
+                return Ok(Some(Event {
+                    r#type: (Type::AccountOwnerChanged(AccountOwnerChanged {
+                        old_owner: pre_balance.map(|token_balance| token_balance.owner.clone()).unwrap_or_default(),
+                        new_owner: new_owner.map(|owner| bs58::encode(owner).into_string()).unwrap_or_default(),
+                        balance_raw: pre_balance
+                            .and_then(|token_balance| token_balance.ui_token_amount.as_ref())
+                            .and_then(|amount| amount.amount.parse().ok())
+                            .unwrap_or_default(),
+                        account,
+                    })),
+                }));
+            }
+            TokenInstruction::CloseAccount => {
+                // CloseAccount does not reference the mint either and a closed account has no post balance, HONEY
+                // accounts are identified from the pre token balances
+                let account = account_at(instruction, 0)?;
+                let Some(token_balance) = honey_token_balance(instruction, params, &meta.pre_token_balances, &account) else {
+                    return Ok(None);
+                };
+
+                // the third account is the close authority, not necessarily the owner, an empty owner is resolved
+                // from the owners store downstream
+                return Ok(Some(Event {
+                    r#type: (Type::ClosedAccount(ClosedAccount {
+                        destination: account_at(instruction, 1)?,
+                        owner: token_balance.owner.clone(),
+                        account,
+                    })),
+                }));
+            }
             _ => {}
         },
     }
@@ -837,17 +1058,241 @@ pub fn process_token_instruction(
     return Ok(None);
 }
 
+fn event_to_item(ev: Event) -> Item {
+    match ev.r#type {
+        Type::Mint(mint) => Item::Mint(mint),
+        Type::Burn(burn) => Item::Burn(burn),
+        Type::Transfer(transfer) => Item::Transfer(transfer),
+        Type::InitializeAccount(initialize_account) => Item::InitializedAccount(initialize_account),
+        Type::AccountOwnerChanged(account_owner_changed) => Item::AccountOwnerChanged(account_owner_changed),
+        Type::ClosedAccount(closed_account) => Item::ClosedAccount(closed_account),
+    }
+}
+
+/// Wraps an item produced directly by a top-level instruction.
+fn top_level_output(item: Item) -> Instruction {
+    Instruction {
+        item: Some(item),
+        stack_height: 1,
+        ..Default::default()
+    }
+}
+
+/// Wraps an item produced by the inner instruction at `inner_index` of `compile_instruction`, resolving
+/// the program that invoked it from the stack heights of the preceding inner instructions.
+fn inner_instruction_output(compile_instruction: &InstructionView, inner_index: usize, item: Item) -> Instruction {
+    let inner_instructions: Vec<InstructionView> = compile_instruction.inner_instructions().collect();
+    let stack_height = inner_instructions
+        .get(inner_index)
+        .map(|inner| inner.stack_height())
+        .unwrap_or_default();
+
+    let invoking_program_id = inner_instructions
+        .iter()
+        .take(inner_index)
+        .rev()
+        .find(|candidate| candidate.stack_height() < stack_height)
+        .map(|candidate| candidate.program_id().to_string())
+        .unwrap_or_else(|| compile_instruction.program_id().to_string());
+
+    Instruction {
+        item: Some(item),
+        inner_index: Some(inner_index as u32),
+        stack_height,
+        invoking_program_id,
+        ..Default::default()
+    }
+}
+
+fn hivemapper_discriminator(instruction: &InstructionView, params: &Params) -> String {
+    match HivemapperProgram::from_program_id(params, &instruction.program_id().to_string()) {
+        Some(_) => Hex(discriminator_bytes(instruction.data())).to_string(),
+        None => "".to_string(),
+    }
+}
+
+fn hivemapper_instruction_name(instruction: &InstructionView, params: &Params) -> String {
+    instructions::lookup(params, &instruction.program_id().to_string(), instruction.data())
+        .map(|(entry, _)| entry.instruction.name().to_string())
+        .unwrap_or_default()
+}
+
+/// Kind of HONEY token instruction, `mint`, `burn` or `other`, made by the inner instruction at `inner_index`.
+fn token_instruction_kind(
+    compile_instruction: &InstructionView,
+    inner_index: usize,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+) -> Result<&'static str, InstructionError> {
+    match process_token_instruction(&inner_instruction(compile_instruction, inner_index)?, meta, params)? {
+        Some(Event { r#type: Type::Mint(_) }) => Ok("mint"),
+        Some(Event { r#type: Type::Burn(_) }) => Ok("burn"),
+        _ => Ok("other"),
+    }
+}
+
+fn known_hivemapper_instruction(instruction: &InstructionView, params: &Params) -> Option<HivemapperInstruction> {
+    let (entry, matched) = instructions::lookup(params, &instruction.program_id().to_string(), instruction.data())?;
+    if matched == Match::FirstByte {
+        log::info!(
+            "trx_hash {} instruction {} of program {} matched on the first byte of discriminator {} only",
+            bs58::encode(instruction.transaction().hash()).into_string(),
+            entry.instruction.name(),
+            instruction.program_id().to_string(),
+            Hex(discriminator_bytes(instruction.data())).to_string()
+        );
+    }
+
+    Some(entry.instruction)
+}
+
+/// Output of an instruction found in the IDL files without a dedicated decoder, arguments and accounts are named
+/// after the IDL.
+fn idl_instruction_output(
+    instruction: &InstructionView,
+    hivemapper_instruction: HivemapperInstruction,
+) -> Result<Instruction, InstructionError> {
+    let args = hivemapper_instruction
+        .decode_args(instruction.data())?
+        .into_iter()
+        .map(|(name, value)| IdlArgument { name: name.to_string(), value })
+        .collect();
+
+    let accounts = instruction
+        .accounts()
+        .iter()
+        .enumerate()
+        .map(|(index, account)| IdlAccount {
+            name: hivemapper_instruction.account_name(index).unwrap_or_default().to_string(),
+            address: account.to_string(),
+        })
+        .collect();
+
+    Ok(top_level_output(Item::IdlInstruction(IdlInstruction {
+        program_id: instruction.program_id().to_string(),
+        name: hivemapper_instruction.name().to_string(),
+        args,
+        accounts,
+    })))
+}
+
+fn unknown_instruction_output(instruction: &InstructionView) -> Instruction {
+    let inner_instructions = instruction
+        .inner_instructions()
+        .map(|inner| InnerInstructionSummary {
+            program_id: inner.program_id().to_string(),
+            stack_height: inner.stack_height(),
+            data: Hex(inner.data()).to_string(),
+            accounts: inner.accounts().iter().map(|account| account.to_string()).collect(),
+        })
+        .collect();
+
+    top_level_output(Item::UnknownInstruction(UnknownInstruction {
+        program_id: instruction.program_id().to_string(),
+        discriminator: Hex(discriminator_bytes(instruction.data())).to_string(),
+        data: Hex(instruction.data()).to_string(),
+        accounts: instruction.accounts().iter().map(|account| account.to_string()).collect(),
+        inner_instructions,
+    }))
+}
+
+fn discriminator_bytes(data: &[u8]) -> &[u8] {
+    &data[..data.len().min(ANCHOR_DISCRIMINATOR_LEN)]
+}
+
+fn inner_instruction<'a>(instruction: &InstructionView<'a>, index: usize) -> Result<InstructionView<'a>, InstructionError> {
+    instruction
+        .inner_instructions()
+        .nth(index)
+        .ok_or(InstructionError::MissingInnerInstruction(index))
+}
+
+fn account_at(instruction: &InstructionView, index: usize) -> Result<String, InstructionError> {
+    instruction
+        .accounts()
+        .get(index)
+        .map(|account| account.to_string())
+        .ok_or(InstructionError::MissingAccount(index))
+}
 
 fn amount_to_decimals(amount: f64, decimal: f64) -> f64 {
     let base: f64 = 10.0;
     return amount.div(&(base.powf(decimal)));
 }
 
-pub fn is_honey_token_transfer(pre_token_balances: &Vec<TokenBalance>, account: &Address) -> bool {
-    for token_balance in pre_token_balances.iter() {
-        if token_balance.owner.eq(account.to_string().as_str()) && token_balance.mint.eq(constants::HONEY_CONTRACT_ADDRESS) {
-            return true;
-        }
+/// Formats a base-unit amount as an exact decimal string, e.g. 1500000000 with 9 decimals is "1.500000000".
+fn amount_to_decimal_string(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (units, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{}.{}", units, fraction)
+}
+
+/// Resolves the wallet owning a HONEY token account from the transaction token balances. Post balances are
+/// looked up first as they also cover accounts created within the transaction, an empty string is returned
+/// when the account has no balance entry.
+fn token_account_owner(
+    instruction: &InstructionView,
+    meta: &TransactionStatusMeta,
+    params: &Params,
+    token_account: &str,
+) -> String {
+    honey_token_balance(instruction, params, &meta.post_token_balances, token_account)
+        .or_else(|| honey_token_balance(instruction, params, &meta.pre_token_balances, token_account))
+        .map(|token_balance| token_balance.owner.clone())
+        .unwrap_or_default()
+}
+
+/// Finds the HONEY balance entry of `token_account` among the given transaction token balances.
+fn honey_token_balance<'a>(
+    instruction: &InstructionView,
+    params: &Params,
+    token_balances: &'a Vec<TokenBalance>,
+    token_account: &str,
+) -> Option<&'a TokenBalance> {
+    let accounts = instruction.transaction().resolved_accounts();
+    token_balances
+        .iter()
+        .filter(|token_balance| token_balance.mint == params.mint)
+        .find(|token_balance| {
+            accounts
+                .get(token_balance.account_index as usize)
+                .map(|account| bs58::encode(account).into_string() == token_account)
+                .unwrap_or(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::amount_to_decimal_string;
+
+    #[test]
+    fn amount_to_decimal_string_without_decimals() {
+        assert_eq!(amount_to_decimal_string(0, 0), "0");
+        assert_eq!(amount_to_decimal_string(1500, 0), "1500");
+    }
+
+    #[test]
+    fn amount_to_decimal_string_pads_amounts_shorter_than_decimals() {
+        assert_eq!(amount_to_decimal_string(0, 9), "0.000000000");
+        assert_eq!(amount_to_decimal_string(1, 9), "0.000000001");
+        assert_eq!(amount_to_decimal_string(123, 6), "0.000123");
+    }
+
+    #[test]
+    fn amount_to_decimal_string_keeps_trailing_zeros() {
+        assert_eq!(amount_to_decimal_string(1_500_000_000, 9), "1.500000000");
+        assert_eq!(amount_to_decimal_string(1_000_000, 6), "1.000000");
+    }
+
+    #[test]
+    fn amount_to_decimal_string_max_amount() {
+        assert_eq!(amount_to_decimal_string(u64::MAX, 0), "18446744073709551615");
+        assert_eq!(amount_to_decimal_string(u64::MAX, 9), "18446744073.709551615");
+        assert_eq!(amount_to_decimal_string(u64::MAX, 20), "0.18446744073709551615");
+        assert_eq!(amount_to_decimal_string(u64::MAX, u8::MAX), format!("0.{:0>255}", u64::MAX));
     }
-    return false;
-}
\ No newline at end of file
+}
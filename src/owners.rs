@@ -0,0 +1,30 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::Transactions;
+use substreams::store::{StoreDelete, StoreNew, StoreSet, StoreSetString};
+
+/// Keeps the wallet owning each HONEY token account, keyed by token account address. Closed accounts are removed.
+///
+/// Owner changes and closes are only seen in transactions selected by the foundational filter, which matches on the
+/// HONEY mint while SetAuthority and CloseAccount don't reference it: a standalone owner change is missed and its
+/// account keeps the old owner, a standalone close is missed and its account is never removed.
+#[substreams::handlers::store]
+pub fn store_account_owners(transactions: Transactions, store: StoreSetString) {
+    let mut ordinal = 0;
+    for trx in transactions.transactions {
+        for instruction in trx.instructions {
+            match instruction.item {
+                Some(Item::InitializedAccount(initialized_account)) => {
+                    store.set(ordinal, &initialized_account.account, &initialized_account.owner);
+                }
+                Some(Item::AccountOwnerChanged(account_owner_changed)) => {
+                    store.set(ordinal, &account_owner_changed.account, &account_owner_changed.new_owner);
+                }
+                Some(Item::ClosedAccount(closed_account)) => {
+                    store.delete_prefix(ordinal as i64, &closed_account.account);
+                }
+                _ => continue,
+            }
+            ordinal += 1;
+        }
+    }
+}
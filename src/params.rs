@@ -0,0 +1,152 @@
+use anyhow::anyhow;
+use substreams::errors::Error;
+
+use crate::constants;
+
+/// How `map_outputs` reacts to instructions it can't decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Fails the module on the first decode error or unknown Hivemapper instruction, for regression checks.
+    Strict,
+    /// Records decode errors and unknown instructions in the output and keeps going, for production sinks.
+    Lenient,
+}
+
+/// `map_outputs` params, given as `key=value` pairs separated by `&`, e.g. `mode=strict&decimals=9`. Keys left out
+/// keep their mainnet default.
+#[derive(Clone, Debug)]
+pub struct Params {
+    pub mode: Mode,
+    /// Hivemapper program id, `program` key.
+    pub program_id: String,
+    /// Hivemapper lib program id, `lib` key.
+    pub lib_program_id: String,
+    /// HONEY mint address, `mint` key.
+    pub mint: String,
+    /// HONEY mint decimals, `decimals` key.
+    pub decimals: u8,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            mode: Mode::Lenient,
+            program_id: constants::HONEY_TOKEN_INSTRUCTION_PROGRAM.to_string(),
+            lib_program_id: constants::HONEY_TOKEN_INSTRUCTION_LIB.to_string(),
+            mint: constants::HONEY_CONTRACT_ADDRESS.to_string(),
+            decimals: constants::HONEY_TOKEN_DECIMALS,
+        }
+    }
+}
+
+impl Params {
+    pub fn parse(params: &str) -> Result<Params, Error> {
+        let mut parsed = Params::default();
+
+        for pair in params.split('&').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid param {:?}, expecting key=value", pair))?;
+
+            match key.trim() {
+                "mode" => {
+                    parsed.mode = match value.trim() {
+                        "strict" => Mode::Strict,
+                        "lenient" => Mode::Lenient,
+                        other => return Err(anyhow!("invalid mode {:?}, expecting strict or lenient", other)),
+                    }
+                }
+                "program" => parsed.program_id = address(value)?,
+                "lib" => parsed.lib_program_id = address(value)?,
+                "mint" => parsed.mint = address(value)?,
+                "decimals" => {
+                    parsed.decimals = value
+                        .trim()
+                        .parse()
+                        .map_err(|err| anyhow!("invalid decimals {:?}: {}", value, err))?
+                }
+                other => return Err(anyhow!("unknown param {:?}", other)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn strict(&self) -> bool {
+        self.mode == Mode::Strict
+    }
+}
+
+fn address(value: &str) -> Result<String, Error> {
+    let value = value.trim();
+    match bs58::decode(value).into_vec() {
+        Ok(bytes) if bytes.len() == 32 => Ok(value.to_string()),
+        _ => Err(anyhow!("invalid address {:?}, expecting a base58 encoded public key", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+    #[test]
+    fn parse_empty_params_keeps_defaults() {
+        let params = Params::parse("").unwrap();
+
+        assert_eq!(params.mode, Mode::Lenient);
+        assert_eq!(params.program_id, constants::HONEY_TOKEN_INSTRUCTION_PROGRAM);
+        assert_eq!(params.lib_program_id, constants::HONEY_TOKEN_INSTRUCTION_LIB);
+        assert_eq!(params.mint, constants::HONEY_CONTRACT_ADDRESS);
+        assert_eq!(params.decimals, constants::HONEY_TOKEN_DECIMALS);
+    }
+
+    #[test]
+    fn parse_overrides() {
+        let params = Params::parse(&format!("mode=strict&mint={}&decimals=6", WRAPPED_SOL_MINT)).unwrap();
+
+        assert!(params.strict());
+        assert_eq!(params.mint, WRAPPED_SOL_MINT);
+        assert_eq!(params.decimals, 6);
+        assert_eq!(params.program_id, constants::HONEY_TOKEN_INSTRUCTION_PROGRAM);
+    }
+
+    #[test]
+    fn parse_unknown_key() {
+        let err = Params::parse("mode=strict&network=devnet").unwrap_err();
+
+        assert_eq!(err.to_string(), r#"unknown param "network""#);
+    }
+
+    #[test]
+    fn parse_bad_mode() {
+        let err = Params::parse("mode=fast").unwrap_err();
+
+        assert_eq!(err.to_string(), r#"invalid mode "fast", expecting strict or lenient"#);
+    }
+
+    #[test]
+    fn parse_missing_value() {
+        let err = Params::parse("strict").unwrap_err();
+
+        assert_eq!(err.to_string(), r#"invalid param "strict", expecting key=value"#);
+    }
+
+    #[test]
+    fn parse_bad_address() {
+        // 0, O, I and l are not in the base58 alphabet
+        let err = Params::parse("mint=0OIl").unwrap_err();
+        assert_eq!(err.to_string(), r#"invalid address "0OIl", expecting a base58 encoded public key"#);
+
+        // valid base58 but not 32 bytes long
+        assert!(Params::parse("program=abc").is_err());
+    }
+
+    #[test]
+    fn parse_out_of_range_decimals() {
+        assert!(Params::parse("decimals=255").is_ok());
+        assert!(Params::parse("decimals=256").is_err());
+        assert!(Params::parse("decimals=-1").is_err());
+    }
+}
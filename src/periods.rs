@@ -0,0 +1,25 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{PaymentPeriod, Transactions};
+use substreams::store::{StoreNew, StoreSet, StoreSetProto};
+
+/// Hivemapper payout periods, keyed by `default:<period id>` and `payment:<period id>` since nothing
+/// guarantees a default and a payment period never share an id.
+///
+/// Stays empty until the period layout is confirmed and `map_outputs` emits PaymentPeriod items again.
+#[substreams::handlers::store]
+pub fn store_payment_periods(transactions: Transactions, store: StoreSetProto<PaymentPeriod>) {
+    let mut ordinal = 0;
+    for trx in transactions.transactions {
+        for instruction in trx.instructions {
+            if let Some(Item::PaymentPeriod(payment_period)) = instruction.item {
+                let key = format!(
+                    "{}:{}",
+                    payment_period.r#type().as_str_name().to_lowercase(),
+                    payment_period.period_id
+                );
+                store.set(ordinal, key, &payment_period);
+                ordinal += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{QaInvoiceCreated, Transactions};
+use substreams::store::{StoreDelete, StoreNew, StoreSet, StoreSetProto};
+
+/// Imagery QA invoices created but not yet paid, keyed by invoice account.
+///
+/// Stays empty until the QA invoice layouts are confirmed and `map_outputs` emits QA invoice items again.
+#[substreams::handlers::store]
+pub fn store_outstanding_qa_invoices(transactions: Transactions, store: StoreSetProto<QaInvoiceCreated>) {
+    let mut ordinal = 0;
+    for trx in transactions.transactions {
+        for instruction in trx.instructions {
+            match instruction.item {
+                Some(Item::QaInvoiceCreated(qa_invoice_created)) => {
+                    store.set(ordinal, &qa_invoice_created.invoice, &qa_invoice_created);
+                }
+                Some(Item::QaInvoicePaid(qa_invoice_paid)) => {
+                    store.delete_prefix(ordinal as i64, &qa_invoice_paid.invoice);
+                }
+                _ => continue,
+            }
+            ordinal += 1;
+        }
+    }
+}
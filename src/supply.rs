@@ -0,0 +1,82 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{burn, mint, SupplyByType, SupplySnapshot, Transactions};
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+use substreams::scalar::BigInt;
+use substreams::store::{StoreAdd, StoreAddBigInt, StoreGet, StoreGetBigInt, StoreNew};
+
+const MINTED: &str = "minted";
+const BURNED: &str = "burned";
+
+const MINT_TYPES: [mint::Type; 10] = [
+    mint::Type::Unset,
+    mint::Type::Mint,
+    mint::Type::RegularDriver,
+    mint::Type::NoSplit,
+    mint::Type::AiTrainer,
+    mint::Type::Operational,
+    mint::Type::Reward,
+    mint::Type::FleetManager,
+    mint::Type::FleetDriver,
+    mint::Type::MapConsumption,
+];
+
+const BURN_TYPES: [burn::Type; 3] = [burn::Type::Unset, burn::Type::Burn, burn::Type::MapCreate];
+
+/// Cumulative HONEY minted and burned in base units, in total under `minted` and `burned` and per
+/// type under `minted:<Mint.Type>` and `burned:<Burn.Type>`.
+#[substreams::handlers::store]
+pub fn store_supply(transactions: Transactions, store: StoreAddBigInt) {
+    let mut ordinal = 0;
+    for trx in transactions.transactions {
+        for instruction in trx.instructions {
+            match instruction.item {
+                Some(Item::Mint(mint)) => {
+                    let keys = vec![MINTED.to_string(), type_key(MINTED, mint.r#type().as_str_name())];
+                    store.add_many(ordinal, &keys, BigInt::from(mint.amount_raw));
+                }
+                Some(Item::Burn(burn)) => {
+                    let keys = vec![BURNED.to_string(), type_key(BURNED, burn.r#type().as_str_name())];
+                    store.add_many(ordinal, &keys, BigInt::from(burn.amount_raw));
+                }
+                _ => continue,
+            }
+            ordinal += 1;
+        }
+    }
+}
+
+#[substreams::handlers::map]
+pub fn map_supply_snapshots(clock: Clock, supply: StoreGetBigInt) -> Result<SupplySnapshot, Error> {
+    let minted = supply.get_last(MINTED).unwrap_or_else(BigInt::zero);
+    let burned = supply.get_last(BURNED).unwrap_or_else(BigInt::zero);
+
+    Ok(SupplySnapshot {
+        slot: clock.number,
+        timestamp: clock.timestamp,
+        net_supply: (minted.clone() - burned.clone()).to_string(),
+        minted: minted.to_string(),
+        burned: burned.to_string(),
+        minted_by_type: supply_by_type(&supply, MINTED, MINT_TYPES.iter().map(|t| t.as_str_name())),
+        burned_by_type: supply_by_type(&supply, BURNED, BURN_TYPES.iter().map(|t| t.as_str_name())),
+    })
+}
+
+fn supply_by_type<'a>(
+    supply: &StoreGetBigInt,
+    prefix: &str,
+    type_names: impl Iterator<Item = &'a str>,
+) -> Vec<SupplyByType> {
+    type_names
+        .filter_map(|type_name| {
+            supply.get_last(type_key(prefix, type_name)).map(|amount| SupplyByType {
+                r#type: type_name.to_string(),
+                amount: amount.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn type_key(prefix: &str, type_name: &str) -> String {
+    format!("{}:{}", prefix, type_name)
+}
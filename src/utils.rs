@@ -0,0 +1,19 @@
+use crate::pb::hivemapper::types::v1::instruction::Item;
+use crate::pb::hivemapper::types::v1::{Instruction, SplitPayment, Transaction};
+
+/// Identifies an event by its transaction and position, e.g. `<trx_hash>-2-5` for the inner instruction 5 of
+/// the top-level instruction 2, `<trx_hash>-2` when produced by the top-level instruction itself.
+pub fn event_id(trx_hash: &str, instruction: &Instruction) -> String {
+    match instruction.inner_index {
+        Some(inner_index) => format!("{}-{}-{}", trx_hash, instruction.top_level_index, inner_index),
+        None => format!("{}-{}", trx_hash, instruction.top_level_index),
+    }
+}
+
+/// Split payments of the transaction along with their event ID.
+pub fn split_payments(trx: &Transaction) -> impl Iterator<Item = (String, &SplitPayment)> {
+    trx.instructions.iter().filter_map(|instruction| match &instruction.item {
+        Some(Item::SplitPayment(split_payment)) => Some((event_id(&trx.trx_hash, instruction), split_payment)),
+        _ => None,
+    })
+}
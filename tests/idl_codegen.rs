@@ -0,0 +1,113 @@
+#[path = "../build/idl.rs"]
+mod idl;
+
+use idl::Discriminator;
+use sha2::{Digest, Sha256};
+
+const SMALL_IDL: &str = r#"{
+  "instructions": [
+    {
+      "name": "pay_fleet",
+      "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+      "accounts": [{ "name": "unknown_0" }, { "name": "driver" }, { "name": "fleet" }],
+      "args": [{ "name": "amount", "type": "u64" }, { "name": "at", "type": "i64" }]
+    },
+    {
+      "name": "mint",
+      "discriminator_first_byte": 9,
+      "accounts": [],
+      "args": []
+    }
+  ]
+}"#;
+
+#[test]
+fn generate_small_idl() {
+    let instructions = idl::parse(SMALL_IDL, "small.json", "Lib", "lib_");
+    idl::check_first_bytes(&instructions);
+    let code = idl::generate(&instructions);
+
+    for expected in [
+        "pub enum HivemapperInstruction {\n    LibPayFleet,\n    LibMint,\n}",
+        "            Self::LibPayFleet => \"pay_fleet\",",
+        "            (Self::LibPayFleet, 0) => Some(\"unknown_0\"),",
+        "            (Self::LibPayFleet, accounts::lib_pay_fleet::DRIVER) => Some(\"driver\"),",
+        "            (Self::LibPayFleet, accounts::lib_pay_fleet::FLEET) => Some(\"fleet\"),",
+        "                let args = LibPayFleetArgs::decode(data)?;",
+        "                Ok(vec![(\"amount\", args.amount.to_string()), (\"at\", args.at.to_string())])",
+        "            Self::LibMint => Ok(vec![]),",
+        "    RegistryEntry { program: HivemapperProgram::Lib, discriminator: Discriminator::Anchor([1, 2, 3, 4, 5, 6, 7, 8]), instruction: HivemapperInstruction::LibPayFleet },",
+        "    RegistryEntry { program: HivemapperProgram::Lib, discriminator: Discriminator::FirstByte(9), instruction: HivemapperInstruction::LibMint },",
+        "    pub mod lib_pay_fleet {\n        pub const DRIVER: usize = 1;\n        pub const FLEET: usize = 2;\n    }",
+        "pub struct LibPayFleetArgs {\n    pub amount: u64,\n    pub at: i64,\n}",
+        "            amount: args.u64()?,\n            at: args.i64()?,",
+    ] {
+        assert!(code.contains(expected), "missing {:?} in generated code:\n{}", expected, code);
+    }
+    // no role constant for unknown accounts and no module for instructions without roles
+    assert!(!code.contains("UNKNOWN_0"));
+    assert!(!code.contains("pub mod lib_mint"));
+}
+
+#[test]
+#[should_panic(expected = "discriminator must be the 8 bytes Anchor discriminator")]
+fn parse_rejects_partial_discriminators() {
+    idl::parse(
+        r#"{ "instructions": [{ "name": "pay", "discriminator": [1, 2], "accounts": [], "args": [] }] }"#,
+        "partial.json",
+        "Program",
+        "",
+    );
+}
+
+#[test]
+#[should_panic(expected = "needs either a discriminator or a discriminator_first_byte")]
+fn parse_rejects_both_discriminators() {
+    idl::parse(
+        r#"{ "instructions": [{ "name": "pay", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8], "discriminator_first_byte": 1, "accounts": [], "args": [] }] }"#,
+        "both.json",
+        "Program",
+        "",
+    );
+}
+
+#[test]
+#[should_panic(expected = "unsupported IDL argument type")]
+fn generate_rejects_unsupported_argument_types() {
+    let instructions = idl::parse(
+        r#"{ "instructions": [{ "name": "pay", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8], "accounts": [], "args": [{ "name": "to", "type": "pubkey" }] }] }"#,
+        "pubkey.json",
+        "Program",
+        "",
+    );
+    idl::generate(&instructions);
+}
+
+#[test]
+#[should_panic(expected = "instructions pay_fleet and pay of the Lib IDL share the first discriminator byte 0x01")]
+fn check_first_bytes_rejects_shared_first_bytes() {
+    let mut instructions = idl::parse(SMALL_IDL, "small.json", "Lib", "lib_");
+    instructions.extend(idl::parse(
+        r#"{ "instructions": [{ "name": "pay", "discriminator_first_byte": 1, "accounts": [], "args": [] }] }"#,
+        "shared.json",
+        "Lib",
+        "lib_",
+    ));
+    idl::check_first_bytes(&instructions);
+}
+
+/// The Anchor discriminators of the checked-in IDLs are `sha256("global:<name>")[..8]` of their instruction name, see
+/// idls/README.md.
+#[test]
+fn checked_in_discriminators_match_their_names() {
+    let mut instructions = idl::parse(include_str!("../idls/honey_program.json"), "honey_program.json", "Program", "");
+    instructions.extend(idl::parse(include_str!("../idls/honey_lib.json"), "honey_lib.json", "Lib", "lib_"));
+    idl::check_first_bytes(&instructions);
+
+    for instruction in &instructions {
+        if let Discriminator::Anchor(discriminator) = &instruction.discriminator {
+            let hash = Sha256::digest(format!("global:{}", instruction.name).as_bytes());
+            assert_eq!(discriminator[..], hash[..8], "discriminator of {}", instruction.name);
+        }
+    }
+}